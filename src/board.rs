@@ -2,7 +2,7 @@ use std::fmt::Debug;
 
 use crate::{piece::Piece, position::Position};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Board {
     placed: u16,
     board: [u8; 16],
@@ -149,7 +149,7 @@ impl Board {
 
     /// Mutates the current board into having a nominated piece
     pub fn nominate_inplace(&mut self, piece: usize) -> Result<(), QuartoError> {
-        self.nominated = ((self.remaining_pieces & 1 << piece) != 0)
+        self.nominated = (piece < 16 && (self.remaining_pieces & 1 << piece) != 0)
             .then(|| Some(piece))
             .ok_or(QuartoError::PieceNotAvailable)?;
         Ok(())
@@ -166,11 +166,11 @@ impl Board {
 
     // Mutates the current board by placing the nominated piece
     pub fn place_inplace(&mut self, position: Position) -> Result<(), QuartoError> {
-        let nom = self.nominated.take().ok_or(QuartoError::NoneNominated)?;
         let i = position.to_index();
         if self.placed & 1 << i != 0 {
             Err(QuartoError::OccupiedSquare)?
         }
+        let nom = self.nominated.take().ok_or(QuartoError::NoneNominated)?;
 
         self.placed |= 1 << i;
         self.remaining_pieces = self.remaining_pieces & !(1 << nom);
@@ -187,6 +187,27 @@ impl Board {
         }
     }
 
+    // Creates a new board from self by making the move
+    pub fn play(&self, m: Move) -> Result<Board, QuartoError> {
+        match m {
+            Move::Nominate(piece) => self.nominate(piece),
+            Move::Place(position) => self.place(position),
+        }
+    }
+
+    /// Moves available to the player to move, placements when a piece is nominated otherwise
+    /// nominations
+    pub fn moves(&self) -> Vec<Move> {
+        match self.nominated {
+            Some(_) => self
+                .free_spaces()
+                .into_iter()
+                .map(|i| Move::Place(Position::from_index(i).unwrap()))
+                .collect(),
+            None => self.piece_indexes().into_iter().map(Move::Nominate).collect(),
+        }
+    }
+
     pub fn check_four(&self, four: [usize; 4]) -> bool {
         four.iter()
             .map(|&x| self.get_square_index(x))
@@ -197,9 +218,61 @@ impl Board {
     pub fn detect_win(&self) -> bool {
         Board::quartos().iter().any(|&xs| self.check_four(xs))
     }
+
+    /// Zobrist hash of the board, equal boards hash equally regardless of move order
+    pub fn zobrist(&self) -> u64 {
+        let squares = (0..16)
+            .filter(|&i| self.placed & 1 << i != 0)
+            .map(|i| {
+                let piece = Piece(self.board[i]).index().unwrap();
+                ZOBRIST_SQUARES[i * 16 + piece]
+            });
+        let pieces = (0..16)
+            .filter(|&i| self.remaining_pieces & 1 << i != 0)
+            .map(|i| ZOBRIST_REMAINING[i]);
+        let nominated = self.nominated.map(|i| ZOBRIST_NOMINATED[i]);
+
+        squares
+            .chain(pieces)
+            .chain(nominated)
+            .fold(0, |hash, key| hash ^ key)
+    }
+}
+
+/// SplitMix64 step, used to fill the Zobrist tables at compile time
+const fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+const fn zobrist_keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut state = seed;
+    let mut i = 0;
+    while i < N {
+        state = splitmix64(state);
+        keys[i] = state;
+        i += 1;
+    }
+    keys
+}
+
+/// Key per square and piece index, indexed by `square * 16 + piece`
+const ZOBRIST_SQUARES: [u64; 256] = zobrist_keys(0x5175_A270);
+const ZOBRIST_REMAINING: [u64; 16] = zobrist_keys(0x0DD5_0FF5);
+const ZOBRIST_NOMINATED: [u64; 16] = zobrist_keys(0x0000_BEEF);
+
+/// Half of a turn, nominating a piece index for the opponent or placing the nominated piece
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Move {
+    Nominate(usize),
+    Place(Position),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QuartoError {
     NoneNominated,
     PieceNotAvailable,
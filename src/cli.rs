@@ -0,0 +1,979 @@
+//! Command line interface
+//!
+//! Players are given as specs of a kind and options, e.g. `minimax:depth=4,eval=live` or
+//! `process:program=./engine,Depth=3`. See [`USAGE`] for the commands and options
+
+use std::{
+    io,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{Board, Move},
+    clock::TimeControl,
+    engine::Engine,
+    evaluation::{by_name, evaluators, live_lines, SafePieces},
+    game::Game,
+    mcts::{MctsConfig, MctsPlayer},
+    minimax::{MinimaxConfig, MinimaxPlayer},
+    network::TcpPlayer,
+    notation::MoveList,
+    player::{CliPlayer, QuartoPlayer, RandomPlayer},
+    process::{ProcessConfig, ProcessPlayer},
+    runner::{ErrorPolicy, GameOutcome, GameRunner, RunnerResult},
+    solver::Solver,
+    sprt::{Sprt, SprtMatch},
+    tournament::{Pairing, Tournament, TournamentResult},
+};
+
+pub const USAGE: &str = "\
+usage: quarto <command> [arguments] [options]
+
+commands
+  play <seat0> <seat1>        play games between two players, seat 1 nominating first
+      --games <n>             games to play, default 1
+      --concurrency <n>       games played at once, default the available parallelism
+      --abort                 stop at the first game which fails
+      --tc <control>          time control, default 5/move and a day a move for humans
+      --start <position>      start every game from the position
+      --openings <path>       start the games from the positions in the file in turn
+      --to-move <seat>        seat to move in the starting positions
+      --games-out <path>      write each game as a row, CSV for .csv and JSON Lines otherwise
+      --summary <path>        write the totals as a row
+  match <candidate> <baseline>
+                              SPRT match, swapping seats between pairs of games
+      --elo0 <elo>            null hypothesis, default 0
+      --elo1 <elo>            alternative hypothesis, default 10
+      --alpha <p>             default 0.05
+      --beta <p>              default 0.05
+      --batch <n>             pairs of games between tests, default 8
+      --max-games <n>         default 20000
+      --abort                 stop at the first game which fails, otherwise at a batch where
+                              every game fails
+      --tc <control>          time control, default 5/move
+      --openings <path>       start each pair of games from the next position in the file
+  tournament <player>...      every player plays every other with seats swapped
+      --rounds <n>            pairs of games per pairing, default 1
+      --gauntlet              the first player plays every other, who do not play each other
+      --tc <control>          time control, default 5/move
+      --openings <path>       start each pair of games from the next position in the file
+      --games-out <path>      write each game as a row
+      --standings <path>      write the standings as rows
+  analyze <position>          evaluate a position and ask a player for a move
+      --player <player>       default minimax
+      --solve                 solve the position exactly, by default only with 8 or more placed
+  solve <position>            solve a position exactly
+  bench                       time fixed searches
+      --depth <n>             minimax depth, default 8
+  engine                      answer the engine protocol on stdin and stdout
+  help                        print this message
+
+A position is a board such as `..../..../..../.... 0123456789ABCDEF -` or a move list such as
+`0a1 Fb2 7`, optionally starting from a board in brackets. The player to move follows from the
+pieces placed, seat 1 nominating first, unless given with --to-move. Opening files have a
+position on each line, skipping blank lines and lines starting with #
+
+Writing games, summaries and standings needs the output feature
+
+A time control is `<seconds>/move` for each nomination and placement, or `<seconds>+<increment>`
+for the whole game with an increment after each action, e.g. `60+0.5`. A player who runs out of
+time loses
+
+players, given as <kind>[:<key>=<value>,...]
+  random
+  human[:name=<name>]
+  minimax[:depth=<n>,time=<ms>|none,eval=<name>,ties=random|first,seed=<n>|none,
+          threads=<n>,solve=<pieces>|none,name=<name>]
+  mcts[:c=<exploration>,iterations=<n>|none,time=<ms>|none,threads=<n>,seed=<n>|none,
+       name=<name>]
+  process:program=<path>[,arg=<argument>...,name=<name>,<option>=<value>...]
+      other keys are sent to the engine with setoption
+  tcp:address=<host>:<port>
+";
+
+/// Time for each action humans get when no time control is given
+const HUMAN_TIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A player described on the command line, which can build any number of the player
+#[derive(Clone)]
+pub enum PlayerSpec {
+    Random,
+    Human(String),
+    Minimax(MinimaxConfig),
+    Mcts(MctsConfig),
+    Process(ProcessConfig),
+    Tcp(String),
+}
+
+impl PlayerSpec {
+    pub fn build(&self) -> Box<dyn QuartoPlayer> {
+        match self {
+            PlayerSpec::Random => Box::new(RandomPlayer),
+            PlayerSpec::Human(name) => Box::new(CliPlayer::new(name.clone())),
+            PlayerSpec::Minimax(config) => Box::new(MinimaxPlayer::from_config(config.clone())),
+            PlayerSpec::Mcts(config) => Box::new(MctsPlayer::from_config(config.clone())),
+            PlayerSpec::Process(config) => Box::new(ProcessPlayer::from_config(config.clone())),
+            PlayerSpec::Tcp(address) => Box::new(TcpPlayer::new(address.clone())),
+        }
+    }
+
+    /// Time control when none is given, humans get a day for each action
+    pub fn default_time_control(&self) -> TimeControl {
+        match self {
+            PlayerSpec::Human(_) => TimeControl::PerMove(HUMAN_TIME),
+            _ => TimeControl::default(),
+        }
+    }
+
+    /// Name for standings, which remote players may replace once connected
+    pub fn name(&self) -> String {
+        match self {
+            PlayerSpec::Random => "RandomPlayer".to_string(),
+            PlayerSpec::Human(name) => name.clone(),
+            PlayerSpec::Minimax(config) => config.identifier(),
+            PlayerSpec::Mcts(config) => config.identifier(),
+            PlayerSpec::Process(config) => config
+                .name
+                .clone()
+                .unwrap_or_else(|| config.program.clone()),
+            PlayerSpec::Tcp(address) => address.clone(),
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {value} for {key}"))
+}
+
+/// Parses a value where `none` leaves the option unset
+fn parse_optional<T: FromStr>(key: &str, value: &str) -> Result<Option<T>, String> {
+    match value {
+        "none" => Ok(None),
+        _ => parse_value(key, value).map(Some),
+    }
+}
+
+fn parse_millis(key: &str, value: &str) -> Result<Option<Duration>, String> {
+    Ok(parse_optional(key, value)?.map(Duration::from_millis))
+}
+
+impl FromStr for PlayerSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = s.split_once(':').unwrap_or((s, ""));
+        let options = rest
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.split_once('=')
+                    .ok_or_else(|| format!("expected <key>=<value> in {s}, found {x}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let unknown = |key: &str| format!("unknown option {key} for {kind}");
+
+        match kind {
+            "random" => match options.first() {
+                Some((key, _)) => Err(unknown(key)),
+                None => Ok(PlayerSpec::Random),
+            },
+            "human" => {
+                let mut name = "Human".to_string();
+                for (key, value) in options {
+                    match key {
+                        "name" => name = value.to_string(),
+                        _ => Err(unknown(key))?,
+                    }
+                }
+                Ok(PlayerSpec::Human(name))
+            }
+            "minimax" => {
+                let mut config = MinimaxConfig::default();
+                for (key, value) in options {
+                    match key {
+                        "depth" => {
+                            config.depth =
+                                parse_value::<usize>(key, value)?.clamp(1, MinimaxPlayer::MAX_DEPTH)
+                        }
+                        "time" => config.time_limit = parse_millis(key, value)?,
+                        "eval" => {
+                            config.evaluator = by_name(value)
+                                .ok_or_else(|| format!("invalid value {value} for {key}"))?
+                        }
+                        "ties" => {
+                            config.random_ties = match value {
+                                "random" => true,
+                                "first" => false,
+                                _ => Err(format!("invalid value {value} for {key}"))?,
+                            }
+                        }
+                        "seed" => config.seed = parse_optional(key, value)?,
+                        "threads" => config.threads = parse_optional(key, value)?,
+                        "solve" => config.solve_from = parse_optional(key, value)?,
+                        "name" => config.name = Some(value.to_string()),
+                        _ => Err(unknown(key))?,
+                    }
+                }
+                Ok(PlayerSpec::Minimax(config))
+            }
+            "mcts" => {
+                let mut config = MctsConfig::default();
+                for (key, value) in options {
+                    match key {
+                        "c" => config.exploration = parse_value(key, value)?,
+                        "iterations" => config.iterations = parse_optional(key, value)?,
+                        "time" => config.time_limit = parse_millis(key, value)?,
+                        "threads" => config.threads = parse_optional(key, value)?,
+                        "seed" => config.seed = parse_optional(key, value)?,
+                        "name" => config.name = Some(value.to_string()),
+                        _ => Err(unknown(key))?,
+                    }
+                }
+                Ok(PlayerSpec::Mcts(config))
+            }
+            "process" => {
+                let mut config = ProcessConfig::default();
+                for (key, value) in options {
+                    match key {
+                        "program" => config.program = value.to_string(),
+                        "arg" => config.args.push(value.to_string()),
+                        "name" => config.name = Some(value.to_string()),
+                        _ => config.options.push((key.to_string(), value.to_string())),
+                    }
+                }
+                match config.program.is_empty() {
+                    true => Err("process players need a program".to_string()),
+                    false => Ok(PlayerSpec::Process(config)),
+                }
+            }
+            "tcp" => {
+                let mut address = None;
+                for (key, value) in options {
+                    match key {
+                        "address" => address = Some(value.to_string()),
+                        _ => Err(unknown(key))?,
+                    }
+                }
+                address
+                    .map(PlayerSpec::Tcp)
+                    .ok_or_else(|| "tcp players need an address".to_string())
+            }
+            _ => Err(format!("unknown player {kind}")),
+        }
+    }
+}
+
+/// Positional arguments and options of a command
+#[derive(Debug, Default)]
+pub struct Args {
+    pub positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    /// Splits `args` given the options of the command and whether each takes a value
+    pub fn parse(args: &[String], allowed: &[(&str, bool)]) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            let (name, inline) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (option, None),
+            };
+            let &(_, takes_value) = allowed
+                .iter()
+                .find(|x| x.0 == name)
+                .ok_or_else(|| format!("unknown option --{name}"))?;
+            let value = match (takes_value, inline) {
+                (true, Some(value)) => Some(value),
+                (true, None) => Some(
+                    args.next()
+                        .ok_or_else(|| format!("--{name} needs a value"))?
+                        .clone(),
+                ),
+                (false, Some(_)) => Err(format!("--{name} does not take a value"))?,
+                (false, None) => None,
+            };
+            parsed.options.push((name.to_string(), value));
+        }
+        Ok(parsed)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|x| x.0 == name)
+    }
+
+    /// Last value given for the option
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|x| x.0 == name)
+            .and_then(|x| x.1.as_deref())
+    }
+
+    pub fn parsed<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.value(name) {
+            Some(value) => parse_value(&format!("--{name}"), value),
+            None => Ok(default),
+        }
+    }
+
+    /// Exactly `n` positional arguments
+    fn expect(&self, n: usize, usage: &str) -> Result<&[String], String> {
+        match self.positional.len() == n {
+            true => Ok(&self.positional),
+            false => Err(format!("expected {usage}")),
+        }
+    }
+}
+
+/// Parses `<seconds>/move` as time per move and `<seconds>+<increment>` as time for the game
+pub fn parse_time_control(s: &str) -> Result<TimeControl, String> {
+    let invalid = || format!("invalid time control {s}");
+    let seconds = |x: &str| {
+        x.parse::<f64>()
+            .ok()
+            .and_then(|x| Duration::try_from_secs_f64(x).ok())
+            .ok_or_else(invalid)
+    };
+    match (s.strip_suffix("/move"), s.split_once('+')) {
+        (Some(time), _) => Ok(TimeControl::PerMove(seconds(time)?)),
+        (None, Some((time, increment))) => Ok(TimeControl::Total {
+            time: seconds(time)?,
+            increment: seconds(increment)?,
+        }),
+        (None, None) => Err(invalid()),
+    }
+}
+
+/// Time control given with `--tc`, the default otherwise
+fn time_control(args: &Args) -> Result<TimeControl, String> {
+    args.value("tc")
+        .map_or(Ok(TimeControl::default()), parse_time_control)
+}
+
+/// A board in notation, or the board reached by a move list
+pub fn parse_position(s: &str) -> Result<Board, String> {
+    let s = s.trim();
+    if s.contains('/') && !s.starts_with('[') {
+        return s.parse().map_err(|e| format!("invalid board {s}: {e}"));
+    }
+    let list = s
+        .parse::<MoveList>()
+        .map_err(|e| format!("invalid move list {s}: {e}"))?;
+    Ok(*list.replay().unwrap().last().unwrap())
+}
+
+/// Positions in a file, one per line, skipping blank lines and `#` comments
+pub fn read_openings(path: &str) -> Result<Vec<Board>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {path}: {e}"))?;
+    let openings = text
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .map(parse_position)
+        .collect::<Result<Vec<_>, _>>()?;
+    match openings.is_empty() {
+        true => Err(format!("no positions in {path}")),
+        false => Ok(openings),
+    }
+}
+
+/// Openings given with `--openings`, none otherwise
+fn openings(args: &Args) -> Result<Vec<Board>, String> {
+    args.value("openings").map_or(Ok(Vec::new()), read_openings)
+}
+
+#[cfg(feature = "output")]
+fn game_writer(path: Option<&str>) -> Result<impl FnMut(&GameOutcome), String> {
+    use crate::output::{GameRow, RowWriter};
+
+    let mut writer = path
+        .map(|path| RowWriter::create(path).map_err(|e| format!("cannot create {path}: {e}")))
+        .transpose()?;
+    Ok(move |game: &GameOutcome| {
+        if let Some(writer) = writer.as_mut() {
+            if let Err(e) = writer
+                .write(&GameRow::from(game))
+                .and_then(|_| writer.flush())
+            {
+                eprintln!("failed to write game {}: {e}", game.0);
+            }
+        }
+    })
+}
+
+#[cfg(not(feature = "output"))]
+fn game_writer(path: Option<&str>) -> Result<impl FnMut(&GameOutcome), String> {
+    match path {
+        Some(_) => Err(NO_OUTPUT.to_string()),
+        None => Ok(|_: &GameOutcome| ()),
+    }
+}
+
+#[cfg(not(feature = "output"))]
+const NO_OUTPUT: &str = "writing rows needs the output feature";
+
+#[cfg(feature = "output")]
+fn write_rows<T: serde::Serialize>(path: &str, rows: &[T]) -> Result<(), String> {
+    let mut writer =
+        crate::output::RowWriter::create(path).map_err(|e| format!("cannot create {path}: {e}"))?;
+    rows.iter()
+        .try_for_each(|row| writer.write(row))
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("failed to write {path}: {e}"))
+}
+
+fn write_summary(path: Option<&str>, result: &RunnerResult) -> Result<(), String> {
+    let Some(path) = path else { return Ok(()) };
+    #[cfg(feature = "output")]
+    return write_rows(path, &[crate::output::RunSummary::from(result)]);
+    #[cfg(not(feature = "output"))]
+    {
+        let _ = (path, result);
+        Err(NO_OUTPUT.to_string())
+    }
+}
+
+fn write_standings(path: Option<&str>, result: &TournamentResult) -> Result<(), String> {
+    let Some(path) = path else { return Ok(()) };
+    #[cfg(feature = "output")]
+    return write_rows(path, &crate::output::StandingRow::rows(result));
+    #[cfg(not(feature = "output"))]
+    {
+        let _ = (path, result);
+        Err(NO_OUTPUT.to_string())
+    }
+}
+
+/// Describes a finished game on one line, with its moves
+fn describe(outcome: &GameOutcome) -> String {
+    match &outcome.1 {
+        Ok((result, record)) => format!(
+            "game {}: {} vs {}, {result:?}, {}",
+            outcome.0,
+            record.players[0],
+            record.players[1],
+            MoveList::from(record)
+        ),
+        Err(e) => format!("game {}: failed, {e:?}", outcome.0),
+    }
+}
+
+/// Runs the command given by the arguments after the program name
+pub fn run(args: &[String]) -> Result<(), String> {
+    let Some((command, rest)) = args.split_first() else {
+        print!("{USAGE}");
+        return Ok(());
+    };
+    match command.as_str() {
+        "play" => play(rest),
+        "match" => sprt_match(rest),
+        "tournament" => tournament(rest),
+        "analyze" => analyze(rest),
+        "solve" => solve(rest),
+        "bench" => bench(rest),
+        "engine" => {
+            Args::parse(rest, &[])?.expect(0, "no arguments")?;
+            let stdin = io::stdin();
+            Engine::new()
+                .run(stdin.lock(), io::stdout())
+                .map_err(|e| format!("failed to communicate over stdin and stdout: {e}"))
+        }
+        "help" | "--help" | "-h" => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(format!("unknown command {command}")),
+    }
+}
+
+fn play(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &[
+            ("games", true),
+            ("concurrency", true),
+            ("abort", false),
+            ("tc", true),
+            ("start", true),
+            ("openings", true),
+            ("to-move", true),
+            ("games-out", true),
+            ("summary", true),
+        ],
+    )?;
+    let players = args.expect(2, "two players")?;
+    let (seat0, seat1) = (
+        players[0].parse::<PlayerSpec>()?,
+        players[1].parse::<PlayerSpec>()?,
+    );
+
+    let controls = match args.value("tc") {
+        Some(control) => [parse_time_control(control)?; 2],
+        None => [seat0.default_time_control(), seat1.default_time_control()],
+    };
+    let to_move = args
+        .value("to-move")
+        .map(|seat| match seat {
+            "0" => Ok(0),
+            "1" => Ok(1),
+            _ => Err(format!(
+                "invalid seat {seat} for --to-move, expected 0 or 1"
+            )),
+        })
+        .transpose()?;
+    let starts = match (args.value("start"), openings(&args)?) {
+        (Some(_), openings) if !openings.is_empty() => {
+            Err("give either --start or --openings".to_string())?
+        }
+        (Some(position), _) => vec![parse_position(position)?],
+        (None, openings) if openings.is_empty() => vec![Board::new()],
+        (None, openings) => openings,
+    };
+    let next = AtomicUsize::new(0);
+    let mut runner = GameRunner::new(args.parsed("games", 1)?, move || {
+        let start = starts[next.fetch_add(1, Ordering::Relaxed) % starts.len()];
+        let game = Game::new(seat0.build(), seat1.build())
+            .with_start(start)
+            .with_time_control(controls[0])
+            .with_seat_time_control(1, controls[1]);
+        match to_move {
+            Some(seat) => game.with_to_move(seat),
+            None => game,
+        }
+    });
+    if let Some(concurrency) = args.value("concurrency") {
+        runner = runner.with_concurrency(parse_value("--concurrency", concurrency)?);
+    }
+    if args.flag("abort") {
+        runner = runner.with_policy(ErrorPolicy::Abort);
+    }
+    let mut write = game_writer(args.value("games-out"))?;
+    let result = pollster::block_on(runner.run_with(|game| {
+        println!("{}", describe(game));
+        write(game);
+    }));
+
+    println!(
+        "{} games in {:.1}s: seat 0 won {}, seat 1 won {}, {} drawn, {} forfeited, {} lost on time",
+        result.games(),
+        result.time_taken,
+        result.l_wins,
+        result.r_wins,
+        result.draws,
+        result.forfeits,
+        result.time_losses
+    );
+    if !result.failures.is_empty() {
+        println!(
+            "{} failed: {} timed out, {} failed to connect, {} invalid results{}",
+            result.failures.len(),
+            result.timeouts,
+            result.connection_failures,
+            result.invalid_results,
+            match result.aborted {
+                true => ", aborted",
+                false => "",
+            }
+        );
+    }
+    write_summary(args.value("summary"), &result)
+}
+
+fn sprt_match(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &[
+            ("elo0", true),
+            ("elo1", true),
+            ("alpha", true),
+            ("beta", true),
+            ("batch", true),
+            ("max-games", true),
+            ("abort", false),
+            ("tc", true),
+            ("openings", true),
+        ],
+    )?;
+    let players = args.expect(2, "a candidate and a baseline")?;
+    let (candidate, baseline) = (
+        players[0].parse::<PlayerSpec>()?,
+        players[1].parse::<PlayerSpec>()?,
+    );
+    let default = Sprt::default();
+    let sprt = Sprt {
+        elo0: args.parsed("elo0", default.elo0)?,
+        elo1: args.parsed("elo1", default.elo1)?,
+        alpha: args.parsed("alpha", default.alpha)?,
+        beta: args.parsed("beta", default.beta)?,
+    };
+
+    println!("{} vs {}", candidate.name(), baseline.name());
+    let mut sprt_match = SprtMatch::new(sprt, move || candidate.build(), move || baseline.build())
+        .with_batch(args.parsed("batch", SprtMatch::DEFAULT_BATCH)?)
+        .with_max_games(args.parsed("max-games", SprtMatch::DEFAULT_MAX_GAMES)?)
+        .with_time_control(time_control(&args)?)
+        .with_openings(openings(&args)?);
+    if args.flag("abort") {
+        sprt_match = sprt_match.with_policy(ErrorPolicy::Abort);
+    }
+    let result = pollster::block_on(sprt_match.run());
+    println!("{result}");
+    if result.failures > 0 {
+        println!(
+            "{} games failed{}",
+            result.failures,
+            match result.aborted {
+                true => ", stopped early",
+                false => "",
+            }
+        );
+    }
+    Ok(())
+}
+
+fn tournament(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &[
+            ("rounds", true),
+            ("gauntlet", false),
+            ("tc", true),
+            ("openings", true),
+            ("games-out", true),
+            ("standings", true),
+        ],
+    )?;
+    if args.positional.len() < 2 {
+        return Err("expected at least two players".to_string());
+    }
+    let pairing = match args.flag("gauntlet") {
+        true => Pairing::Gauntlet,
+        false => Pairing::RoundRobin,
+    };
+
+    let mut tournament = Tournament::new(pairing, args.parsed("rounds", 1)?)
+        .with_time_control(time_control(&args)?)
+        .with_openings(openings(&args)?);
+    for spec in args.positional.iter() {
+        let spec = spec.parse::<PlayerSpec>()?;
+        tournament = tournament.entrant(spec.name(), move || spec.build());
+    }
+    let mut write = game_writer(args.value("games-out"))?;
+    let result = pollster::block_on(tournament.run_with(|game| {
+        println!("{}", describe(game));
+        write(game);
+    }));
+    print!("{result}");
+    write_standings(args.value("standings"), &result)
+}
+
+fn analyze(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &[("player", true), ("solve", false)])?;
+    let board = parse_position(&args.expect(1, "a position")?[0])?;
+    let spec = args
+        .value("player")
+        .unwrap_or("minimax")
+        .parse::<PlayerSpec>()?;
+
+    println!("position {board}");
+    println!(
+        "{} placed, {} live lines, {} safe pieces",
+        board.placed_count(),
+        live_lines(&board).count(),
+        SafePieces::safe_pieces(&board)
+    );
+    let scores = evaluators()
+        .iter()
+        .map(|x| format!("{} {}", x.name(), x.evaluate(&board)))
+        .collect::<Vec<_>>();
+    println!("evaluations {}", scores.join(", "));
+
+    if board.detect_win() || board.moves().is_empty() {
+        println!("the game is over");
+        return Ok(());
+    }
+    if args.flag("solve") || board.placed_count() >= MinimaxPlayer::DEFAULT_SOLVE_FROM {
+        let solution = Solver::new().solve(&board);
+        println!(
+            "solution {:?} for the player to move, playing {}",
+            solution.outcome,
+            solution.best.unwrap()
+        );
+    }
+
+    let mut player = spec.build();
+    let m = pollster::block_on(async {
+        player
+            .connect()
+            .await
+            .map_err(|_| format!("{} failed to connect", spec.name()))?;
+        let m = match board.nominated_index() {
+            Some(_) => Move::Place(player.place(&board).await),
+            None => Move::Nominate(player.nominate(&board).await),
+        };
+        let identifier = player.identifier().await.to_string();
+        let _ = player.disconnect().await;
+        Ok::<_, String>((identifier, m))
+    })?;
+    println!("{} plays {}", m.0, m.1);
+    Ok(())
+}
+
+fn solve(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &[])?;
+    let board = parse_position(&args.expect(1, "a position")?[0])?;
+    let mut solver = Solver::new();
+    let instant = Instant::now();
+    let solution = solver.solve(&board);
+    println!("position {board}");
+    match solution.best {
+        Some(best) => println!(
+            "{:?} for the player to move, playing {best}",
+            solution.outcome
+        ),
+        None => println!("the game is over, {:?}", solution.outcome),
+    }
+    println!(
+        "{} nodes in {:.3}s",
+        solver.nodes(),
+        instant.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Positions searched by `bench`, as move lists
+pub const BENCH_POSITIONS: [&str; 4] = [
+    "",
+    "0a1 Fb1 6c1 9a2",
+    "0a1 Fb1 6c1 9a2 3c2 Cd2 5b3 Ad3",
+    "0a1 Fb1 6c1 9a2 3c2 Cd2 5b3 Ad3 1a4 Ec4",
+];
+
+fn bench(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &[("depth", true)])?;
+    args.expect(0, "no arguments")?;
+    let config = MinimaxConfig {
+        depth: args.parsed("depth", 8)?,
+        time_limit: None,
+        random_ties: false,
+        seed: Some(0),
+        solve_from: None,
+        ..Default::default()
+    };
+    println!("{}", config.identifier());
+
+    let total = Instant::now();
+    for position in BENCH_POSITIONS {
+        let board = parse_position(position)?;
+        let mut player = MinimaxPlayer::from_config(config.clone());
+        let instant = Instant::now();
+        let m = match board.nominated_index() {
+            Some(_) => Move::Place(player.place(&board)),
+            None => Move::Nominate(player.nominate(&board)),
+        };
+        let searched = instant.elapsed();
+
+        let mut solver = Solver::new();
+        let instant = Instant::now();
+        let solved = (board.placed_count() >= MinimaxPlayer::DEFAULT_SOLVE_FROM)
+            .then(|| solver.solve(&board));
+        let solved = match solved {
+            Some(solution) => format!(
+                ", solved {:?} with {} nodes in {:.3}s",
+                solution.outcome,
+                solver.nodes(),
+                instant.elapsed().as_secs_f64()
+            ),
+            None => String::new(),
+        };
+        println!(
+            "{board}: minimax plays {m} in {:.3}s{solved}",
+            searched.as_secs_f64()
+        );
+    }
+    println!("total {:.3}s", total.elapsed().as_secs_f64());
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        cli::{
+            parse_position, parse_time_control, read_openings, run, Args, PlayerSpec,
+            BENCH_POSITIONS,
+        },
+        clock::TimeControl,
+        solver::tests::endgame,
+    };
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    pub fn player_specs() {
+        let PlayerSpec::Minimax(config) = "minimax:depth=4,time=none,eval=live,ties=first,seed=3"
+            .parse()
+            .unwrap()
+        else {
+            panic!("expected minimax");
+        };
+        assert_eq!(config.depth, 4);
+        assert_eq!(config.time_limit, None);
+        assert_eq!(config.evaluator.name(), "live");
+        assert!(!config.random_ties);
+        assert_eq!(config.seed, Some(3));
+
+        let PlayerSpec::Mcts(config) = "mcts:c=0.5,time=200".parse().unwrap() else {
+            panic!("expected mcts");
+        };
+        assert_eq!(config.exploration, 0.5);
+        assert_eq!(config.time_limit, Some(Duration::from_millis(200)));
+
+        let PlayerSpec::Process(config) = "process:program=./engine,arg=-q,Depth=3,name=e"
+            .parse()
+            .unwrap()
+        else {
+            panic!("expected process");
+        };
+        assert_eq!(config.args, vec!["-q"]);
+        assert_eq!(config.options, vec![("Depth".to_string(), "3".to_string())]);
+        assert_eq!(config.name.as_deref(), Some("e"));
+
+        let spec = "human:name=Ann".parse::<PlayerSpec>().unwrap();
+        assert_eq!(
+            spec.default_time_control(),
+            TimeControl::PerMove(Duration::from_secs(86400))
+        );
+        let spec = "tcp:address=127.0.0.1:9000".parse::<PlayerSpec>().unwrap();
+        assert_eq!(spec.name(), "127.0.0.1:9000");
+        assert_eq!(
+            "random".parse::<PlayerSpec>().unwrap().name(),
+            "RandomPlayer"
+        );
+
+        for (spec, error) in [
+            ("alphazero", "unknown player alphazero"),
+            ("minimax:colour=red", "unknown option colour for minimax"),
+            ("minimax:depth=deep", "invalid value deep for depth"),
+            ("mcts:c", "expected <key>=<value> in mcts:c, found c"),
+            ("process:name=e", "process players need a program"),
+        ] {
+            assert_eq!(spec.parse::<PlayerSpec>().err().unwrap(), error);
+        }
+    }
+
+    #[test]
+    pub fn arguments() {
+        let allowed = [("games", true), ("abort", false)];
+        let args = Args::parse(
+            &strings(&["a", "--games", "3", "b", "--abort", "--games=4"]),
+            &allowed,
+        )
+        .unwrap();
+        assert_eq!(args.positional, vec!["a", "b"]);
+        assert!(args.flag("abort"));
+        assert_eq!(args.parsed("games", 1).unwrap(), 4);
+        assert_eq!(args.parsed("concurrency", 2).unwrap(), 2);
+
+        for (args, error) in [
+            (vec!["--colour"], "unknown option --colour"),
+            (vec!["--games"], "--games needs a value"),
+            (vec!["--abort=yes"], "--abort does not take a value"),
+        ] {
+            assert_eq!(Args::parse(&strings(&args), &allowed).unwrap_err(), error);
+        }
+        assert_eq!(
+            parse_time_control("60+0.5").unwrap(),
+            TimeControl::Total {
+                time: Duration::from_secs(60),
+                increment: Duration::from_millis(500),
+            }
+        );
+        assert_eq!(
+            parse_time_control("0.1/move").unwrap(),
+            TimeControl::PerMove(Duration::from_millis(100))
+        );
+        for control in ["60", "-1/move", "1+x"] {
+            assert!(parse_time_control(control).is_err());
+        }
+
+        let args = Args::parse(&strings(&["--games", "many"]), &allowed).unwrap();
+        assert_eq!(
+            args.parsed("games", 1).unwrap_err(),
+            "invalid value many for --games"
+        );
+    }
+
+    #[test]
+    pub fn positions_and_commands() {
+        assert_eq!(parse_position(BENCH_POSITIONS[3]).unwrap(), endgame());
+        assert_eq!(parse_position(&endgame().to_string()).unwrap(), endgame());
+        assert!(parse_position("0a1 Fa1").is_err());
+
+        run(&strings(&["solve", BENCH_POSITIONS[3]])).unwrap();
+        run(&strings(&["play", "random", "random", "--games", "2"])).unwrap();
+
+        let path = std::env::temp_dir().join(format!("openings-{}.txt", std::process::id()));
+        let text = format!("# Suite\n\n{}\n{}\n", BENCH_POSITIONS[1], endgame());
+        std::fs::write(&path, text).unwrap();
+        let path = path.to_str().unwrap();
+        let openings = read_openings(path).unwrap();
+        assert_eq!(
+            openings,
+            vec![parse_position(BENCH_POSITIONS[1]).unwrap(), endgame()]
+        );
+        run(&strings(&["play", "random", "random", "--openings", path])).unwrap();
+        let start = endgame().to_string();
+        run(&strings(&["play", "random", "random", "--start", &start])).unwrap();
+        run(&strings(&[
+            "play",
+            "random",
+            "random",
+            "--start",
+            &start,
+            "--to-move",
+            "0",
+        ]))
+        .unwrap();
+        assert_eq!(
+            run(&strings(&["play", "random", "random", "--to-move", "2"])).unwrap_err(),
+            "invalid seat 2 for --to-move, expected 0 or 1"
+        );
+        assert_eq!(
+            run(&strings(&[
+                "play",
+                "random",
+                "random",
+                "--start",
+                &start,
+                "--openings",
+                path
+            ]))
+            .unwrap_err(),
+            "give either --start or --openings"
+        );
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(
+            run(&strings(&["play", "random"])).unwrap_err(),
+            "expected two players"
+        );
+        assert_eq!(
+            run(&strings(&["castle"])).unwrap_err(),
+            "unknown command castle"
+        );
+    }
+}
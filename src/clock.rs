@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use crate::{board::Board, game::Game};
+
+/// How much time players have for their actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeControl {
+    /// Every nomination and placement must be made within the duration
+    PerMove(Duration),
+    /// Each player has `time` for the whole game, gaining `increment` after each action
+    Total { time: Duration, increment: Duration },
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl::PerMove(Game::CONN_TIMEOUT)
+    }
+}
+
+/// A player's time under a [`TimeControl`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clock {
+    pub control: TimeControl,
+    /// Time left for the next action before the player's flag falls
+    pub remaining: Duration,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let remaining = match control {
+            TimeControl::PerMove(time) => time,
+            TimeControl::Total { time, .. } => time,
+        };
+        Clock { control, remaining }
+    }
+
+    /// Time gained after each action, none under a per-move control
+    pub fn increment(&self) -> Duration {
+        match self.control {
+            TimeControl::PerMove(_) => Duration::ZERO,
+            TimeControl::Total { increment, .. } => increment,
+        }
+    }
+
+    /// Charges an action taking `elapsed`, false if the flag fell before it was made
+    pub fn spend(&mut self, elapsed: Duration) -> bool {
+        if elapsed > self.remaining {
+            self.remaining = Duration::ZERO;
+            return false;
+        }
+        if let TimeControl::Total { increment, .. } = self.control {
+            self.remaining = self.remaining - elapsed + increment;
+        }
+        true
+    }
+
+    /// Time to spend on the next action on the board, spreading the time left over the
+    /// actions the player still has to make
+    pub fn budget(&self, board: &Board) -> Duration {
+        match self.control {
+            TimeControl::PerMove(_) => self.remaining,
+            TimeControl::Total { increment, .. } => {
+                // Every remaining piece is nominated by one player and placed by the other
+                let actions = board.piece_bits().count_ones().max(1);
+                (self.remaining / actions + increment).min(self.remaining)
+            }
+        }
+    }
+
+    /// The budget less `margin` kept back to return the move, keeping at most half the budget
+    pub fn search_time(&self, board: &Board, margin: Duration) -> Duration {
+        let budget = self.budget(board);
+        budget - margin.min(budget / 2)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        board::Board,
+        clock::{Clock, TimeControl},
+    };
+
+    #[test]
+    pub fn spends_time() {
+        let ms = Duration::from_millis;
+        let mut clock = Clock::new(TimeControl::PerMove(ms(100)));
+        assert!(clock.spend(ms(90)) && clock.spend(ms(90)));
+        assert_eq!(clock.remaining, ms(100));
+        assert_eq!(clock.budget(&Board::new()), ms(100));
+        assert!(!clock.spend(ms(101)));
+
+        let mut clock = Clock::new(TimeControl::Total {
+            time: ms(1600),
+            increment: ms(10),
+        });
+        assert_eq!(clock.budget(&Board::new()), ms(110));
+        assert!(clock.spend(ms(600)) && clock.spend(ms(600)));
+        assert_eq!(clock.remaining, ms(420));
+        assert!(!clock.spend(ms(500)));
+        assert_eq!(clock.remaining, Duration::ZERO);
+    }
+}
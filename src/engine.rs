@@ -0,0 +1,375 @@
+//! Engine mode, answering the [`crate::process`] protocol on stdin and stdout so other programs
+//! can drive our players
+//!
+//! Options set with `setoption` before `isready`
+//! - `Player`, one of `minimax`, `mcts` or `random`
+//! - `Depth`, `Eval` and `SolveFrom` configure minimax, a `SolveFrom` of `0` never solving
+//! - `Exploration` configures mcts
+//! - `Threads` and `Seed`, where `0` and `none` leave them unset
+//! - `MoveTime`, most milliseconds spent on a move, `0` searches for the budget given by each
+//!   request, or a share of the time left without one, less a safety margin
+//!
+//! Problems with a command are reported as `info error <reason>`
+
+use std::{
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+use crate::{
+    board::{Board, Move},
+    clock::{Clock, TimeControl},
+    evaluation::{by_name, evaluators},
+    mcts::{MctsConfig, MctsPlayer},
+    minimax::{MinimaxConfig, MinimaxPlayer},
+    player::{QuartoPlayer, RandomPlayer},
+    position::Position,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EngineKind {
+    #[default]
+    Minimax,
+    Mcts,
+    Random,
+}
+
+enum EnginePlayer {
+    Minimax(MinimaxPlayer),
+    Mcts(MctsPlayer),
+    Random(RandomPlayer),
+}
+
+impl EnginePlayer {
+    fn nominate(&mut self, board: &Board, time_limit: Duration) -> usize {
+        match self {
+            EnginePlayer::Minimax(player) => {
+                player.set_time_limit(Some(time_limit));
+                player.nominate(board)
+            }
+            EnginePlayer::Mcts(player) => {
+                player.set_time_limit(Some(time_limit));
+                player.nominate(board)
+            }
+            EnginePlayer::Random(player) => pollster::block_on(player.nominate(board)),
+        }
+    }
+
+    fn place(&mut self, board: &Board, time_limit: Duration) -> Position {
+        match self {
+            EnginePlayer::Minimax(player) => {
+                player.set_time_limit(Some(time_limit));
+                player.place(board)
+            }
+            EnginePlayer::Mcts(player) => {
+                player.set_time_limit(Some(time_limit));
+                player.place(board)
+            }
+            EnginePlayer::Random(player) => pollster::block_on(player.place(board)),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Engine {
+    kind: EngineKind,
+    minimax: MinimaxConfig,
+    mcts: MctsConfig,
+    move_time: Option<Duration>,
+    /// Built from the options when first needed, and rebuilt once they change
+    player: Option<EnginePlayer>,
+    /// Board for the next request, none until a valid `position` is given
+    board: Option<Board>,
+}
+
+impl Engine {
+    /// Time kept back from each request to send the answer
+    pub const TIME_MARGIN: Duration = MinimaxPlayer::TIME_MARGIN;
+
+    pub fn new() -> Self {
+        Engine::default()
+    }
+
+    fn player(&mut self) -> &mut EnginePlayer {
+        self.player.get_or_insert_with(|| match self.kind {
+            EngineKind::Minimax => {
+                EnginePlayer::Minimax(MinimaxPlayer::from_config(self.minimax.clone()))
+            }
+            EngineKind::Mcts => EnginePlayer::Mcts(MctsPlayer::from_config(self.mcts.clone())),
+            EngineKind::Random => EnginePlayer::Random(RandomPlayer),
+        })
+    }
+
+    /// Writes the identification and available options
+    fn identify(&self, output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "id name quarto {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(
+            output,
+            "option name Player type combo default minimax var minimax var mcts var random"
+        )?;
+        writeln!(
+            output,
+            "option name Depth type spin default {0} min 1 max {0}",
+            MinimaxPlayer::MAX_DEPTH
+        )?;
+        let names = evaluators()
+            .iter()
+            .map(|x| format!("var {}", x.name()))
+            .collect::<Vec<_>>();
+        writeln!(
+            output,
+            "option name Eval type combo default {} {}",
+            self.minimax.evaluator.name(),
+            names.join(" ")
+        )?;
+        writeln!(
+            output,
+            "option name SolveFrom type spin default {} min 0 max 16",
+            MinimaxPlayer::DEFAULT_SOLVE_FROM
+        )?;
+        writeln!(
+            output,
+            "option name Exploration type string default {:.2}",
+            self.mcts.exploration
+        )?;
+        writeln!(
+            output,
+            "option name Threads type spin default 0 min 0 max 1024"
+        )?;
+        writeln!(output, "option name Seed type string default none")?;
+        writeln!(
+            output,
+            "option name MoveTime type spin default 0 min 0 max 3600000"
+        )?;
+        writeln!(output, "quartook")
+    }
+
+    fn set_option(&mut self, argument: &str) -> Result<(), String> {
+        let rest = argument
+            .strip_prefix("name ")
+            .ok_or("expected setoption name <option> value <value>")?;
+        let (name, value) = rest
+            .split_once(" value ")
+            .ok_or("expected setoption name <option> value <value>")?;
+        let (name, value) = (name.trim(), value.trim());
+        let invalid = || format!("invalid value {value} for {name}");
+        let number = |max: usize| {
+            value
+                .parse::<usize>()
+                .ok()
+                .filter(|&x| x <= max)
+                .ok_or_else(invalid)
+        };
+
+        match name.to_lowercase().as_str() {
+            "player" => {
+                self.kind = match value {
+                    "minimax" => EngineKind::Minimax,
+                    "mcts" => EngineKind::Mcts,
+                    "random" => EngineKind::Random,
+                    _ => Err(invalid())?,
+                }
+            }
+            "depth" => self.minimax.depth = number(MinimaxPlayer::MAX_DEPTH)?.max(1),
+            "eval" => self.minimax.evaluator = by_name(value).ok_or_else(invalid)?,
+            "solvefrom" => {
+                self.minimax.solve_from = Some(number(16)?).filter(|&x| x > 0);
+            }
+            "exploration" => self.mcts.exploration = value.parse().map_err(|_| invalid())?,
+            "threads" => {
+                let threads = Some(number(1024)?).filter(|&x| x > 0);
+                self.minimax.threads = threads;
+                self.mcts.threads = threads;
+            }
+            "seed" => {
+                let seed = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid())?),
+                };
+                self.minimax.seed = seed;
+                self.mcts.seed = seed;
+            }
+            "movetime" => {
+                self.move_time = Some(number(3_600_000)?)
+                    .filter(|&x| x > 0)
+                    .map(|x| Duration::from_millis(x as u64));
+            }
+            _ => Err(format!("unknown option {name}"))?,
+        }
+        self.player = None;
+        Ok(())
+    }
+
+    /// Time to search for a request given the clocks, the budget if there is one or else the
+    /// share of the time left for the actions still to make
+    fn time_limit(&self, argument: &str, board: &Board) -> Duration {
+        let words = argument.split_whitespace().collect::<Vec<_>>();
+        let value = |key: &str| {
+            words
+                .chunks(2)
+                .find(|pair| pair[0] == key)
+                .and_then(|pair| pair.get(1)?.parse().ok())
+                .map(Duration::from_millis)
+        };
+        let given = value("budget").or_else(|| {
+            let time = value("time")?;
+            let control = match value("movetime") {
+                Some(time) => TimeControl::PerMove(time),
+                None => TimeControl::Total {
+                    time,
+                    increment: value("inc").unwrap_or_default(),
+                },
+            };
+            Some(Clock { control, remaining: time }.budget(board))
+        });
+        let given = given.map(|time| time.saturating_sub(Self::TIME_MARGIN));
+        match (given, self.move_time) {
+            (Some(given), Some(move_time)) => given.min(move_time),
+            (given, move_time) => given
+                .or(move_time)
+                .unwrap_or(MinimaxPlayer::DEFAULT_TIME_LIMIT),
+        }
+    }
+
+    /// Answers commands until `quit` or the end of the input
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            let line = line.trim();
+            let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+            match command {
+                "quarto" => {
+                    self.player = None;
+                    self.identify(&mut output)?;
+                }
+                "setoption" => {
+                    if let Err(e) = self.set_option(argument) {
+                        writeln!(output, "info error {e}")?;
+                    }
+                }
+                "isready" => {
+                    self.player();
+                    writeln!(output, "readyok")?;
+                }
+                "position" => match argument.parse() {
+                    Ok(board) => self.board = Some(board),
+                    Err(e) => {
+                        self.board = None;
+                        writeln!(output, "info error {e}")?
+                    }
+                },
+                "nominate" | "place" if self.board.is_none() => {
+                    writeln!(output, "info error no valid position to {command} on")?
+                }
+                "nominate" => {
+                    let board = self.board.unwrap();
+                    if board.nominated_index().is_some() || board.piece_bits() == 0 {
+                        writeln!(output, "info error no piece can be nominated on {board}")?;
+                    } else {
+                        let time_limit = self.time_limit(argument, &board);
+                        let piece = self.player().nominate(&board, time_limit);
+                        writeln!(output, "nominate {}", Move::Nominate(piece))?;
+                    }
+                }
+                "place" => {
+                    let board = self.board.unwrap();
+                    if board.nominated_index().is_none() {
+                        writeln!(output, "info error no piece is nominated on {board}")?;
+                    } else {
+                        let time_limit = self.time_limit(argument, &board);
+                        let position = self.player().place(&board, time_limit);
+                        writeln!(output, "place {position}")?;
+                    }
+                }
+                "rejected" | "" => (),
+                "quit" => break,
+                _ => writeln!(output, "info error unknown command {command}")?,
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use crate::{board::Board, engine::Engine, solver::tests::endgame};
+
+    fn answers(engine: &mut Engine, input: &str) -> Vec<String> {
+        let mut output = Vec::new();
+        engine.run(Cursor::new(input), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    #[test]
+    pub fn handshake_and_options() {
+        let mut engine = Engine::new();
+        let lines = answers(&mut engine, "quarto\n");
+        assert!(lines[0].starts_with("id name quarto"));
+        assert!(lines.iter().any(|x| x.starts_with("option name Depth")));
+        assert_eq!(lines.last().unwrap(), "quartook");
+
+        let input = "setoption name Depth value 2\nsetoption name Colour value red\n\
+            setoption name Eval value best\nisready\nquit\nisready\n";
+        assert_eq!(
+            answers(&mut engine, input),
+            vec![
+                "info error unknown option Colour",
+                "info error invalid value best for Eval",
+                "readyok",
+            ]
+        );
+        assert_eq!(engine.minimax.depth, 2);
+    }
+
+    #[test]
+    pub fn time_limits() {
+        let engine = Engine::new();
+        let board = Board::new();
+        let limit = |argument| engine.time_limit(argument, &board) + Engine::TIME_MARGIN;
+        let ms = Duration::from_millis;
+        assert_eq!(limit("time 60000 inc 0 otime 60000 oinc 0 budget 800"), ms(800));
+        assert_eq!(limit("time 5000 inc 0 otime 5000 oinc 0 movetime 5000"), ms(5000));
+        // Sixteen pieces left to nominate and place
+        assert_eq!(limit("time 16000 inc 500 otime 9000 oinc 500"), ms(1500));
+    }
+
+    #[test]
+    pub fn answers_requests() {
+        let mut engine = Engine::new();
+        let board = endgame();
+        let input = format!(
+            "setoption name Depth value 2\nsetoption name Seed value 5\n\
+            position {board}\nnominate time 1000 inc 0 otime 1000 oinc 0 budget 1000\n\
+            place time 1000\nposition {}\nplace time 1000\n",
+            board.nominate(2).unwrap()
+        );
+        let lines = answers(&mut engine, &input);
+        assert_eq!(lines.len(), 3);
+        let piece = lines[0].strip_prefix("nominate ").unwrap();
+        assert!(board
+            .nominate(usize::from_str_radix(piece, 16).unwrap())
+            .is_ok());
+        assert!(lines[1].starts_with("info error no piece is nominated"));
+        let square = lines[2].strip_prefix("place ").unwrap();
+        let position = square.parse().unwrap();
+        assert!(board.nominate(2).unwrap().place(position).is_ok());
+
+        // A bad position clears the board rather than leaving the last one
+        let input = format!(
+            "position nonsense\nnominate\nplace\nposition {board}\nnominate\n"
+        );
+        let lines = answers(&mut engine, &input);
+        assert!(lines[0].starts_with("info error expected 3 fields"));
+        assert_eq!(lines[1], "info error no valid position to nominate on");
+        assert_eq!(lines[2], "info error no valid position to place on");
+        assert!(lines[3].starts_with("nominate "));
+        assert_eq!(engine.board, Some(board));
+    }
+}
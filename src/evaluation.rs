@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use crate::board::Board;
+
+/// Heuristic scoring a board for a search player
+///
+/// Scores are from the perspective of the player searching, search players negate them for
+/// their opponent. A won board should score `f32::INFINITY`
+pub trait Evaluator: 'static + Send + Sync {
+    fn name(&self) -> &str;
+    fn evaluate(&self, board: &Board) -> f32;
+}
+
+/// Every evaluator, selectable by name
+pub fn evaluators() -> Vec<Arc<dyn Evaluator>> {
+    vec![Arc::new(Classic), Arc::new(LiveLines), Arc::new(SafePieces)]
+}
+
+pub fn by_name(name: &str) -> Option<Arc<dyn Evaluator>> {
+    evaluators().into_iter().find(|x| x.name() == name)
+}
+
+/// Lines with three pieces sharing an attribute and a free square, as the shared attribute bits
+/// and the square which completes the line
+pub fn live_lines(board: &Board) -> impl Iterator<Item = (u8, usize)> + '_ {
+    Board::quartos().into_iter().filter_map(|xs| {
+        let mut free = xs.iter().filter(|&&x| board.get_square_index(x).is_none());
+        let square = *free.next()?;
+        if free.next().is_some() {
+            return None;
+        }
+        let common = xs
+            .iter()
+            .filter_map(|&x| board.get_square_index(x))
+            .fold(u8::MAX, |x, y| x & y.0);
+        (common != 0).then_some((common, square))
+    })
+}
+
+/// Free squares plus the most attributes shared along any line
+pub struct Classic;
+
+impl Evaluator for Classic {
+    fn name(&self) -> &str {
+        "classic"
+    }
+
+    fn evaluate(&self, board: &Board) -> f32 {
+        if board.detect_win() {
+            return f32::INFINITY;
+        }
+
+        let count = Board::quartos()
+            .iter()
+            .map(|xs| {
+                let xs = xs.iter().filter_map(|&x| board.get_square_index(x));
+                let common = xs.fold(u8::MAX, |x, y| x & y.0);
+                common.count_ones() as f32
+            })
+            .max_by(|l, r| l.total_cmp(r))
+            .unwrap();
+
+        (16 - board.placed_count()) as f32 + count
+    }
+}
+
+/// Free squares plus the number of lines one piece away from a quarto
+pub struct LiveLines;
+
+impl Evaluator for LiveLines {
+    fn name(&self) -> &str {
+        "live"
+    }
+
+    fn evaluate(&self, board: &Board) -> f32 {
+        if board.detect_win() {
+            return f32::INFINITY;
+        }
+
+        (16 - board.placed_count()) as f32 + live_lines(board).count() as f32
+    }
+}
+
+/// Number of remaining pieces which cannot complete a line wherever they are placed
+pub struct SafePieces;
+
+impl SafePieces {
+    pub fn safe_pieces(board: &Board) -> usize {
+        let common = live_lines(board)
+            .map(|(common, _)| common)
+            .fold(0, |x, y| x | y);
+        board
+            .piece_indexes()
+            .into_iter()
+            .filter(|&i| Board::piece_order()[i] & common == 0)
+            .count()
+    }
+}
+
+impl Evaluator for SafePieces {
+    fn name(&self) -> &str {
+        "safe"
+    }
+
+    fn evaluate(&self, board: &Board) -> f32 {
+        if board.detect_win() {
+            return f32::INFINITY;
+        }
+
+        SafePieces::safe_pieces(board) as f32
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        board::Board,
+        evaluation::{by_name, live_lines, Evaluator, LiveLines, SafePieces},
+        game::tests::play_piece,
+    };
+
+    #[test]
+    pub fn live_lines_and_safe_pieces() {
+        let mut board = Board::new();
+        // Pieces 0, 1 and 2 share the top two attributes along the top row
+        play_piece(&mut board, 0, 0, 0);
+        play_piece(&mut board, 1, 0, 1);
+        play_piece(&mut board, 2, 0, 2);
+
+        let lines = live_lines(&board).collect::<Vec<_>>();
+        assert_eq!(lines, vec![(0b01010000, 3)]);
+        assert_eq!(LiveLines.evaluate(&board), 13.0 + 1.0);
+
+        // Only pieces 12 to 15 differ from the row in both attributes
+        assert_eq!(SafePieces::safe_pieces(&board), 4);
+
+        play_piece(&mut board, 3, 0, 3);
+        assert!(live_lines(&board).next().is_none());
+        assert_eq!(SafePieces.evaluate(&board), f32::INFINITY);
+    }
+
+    #[test]
+    pub fn names() {
+        for name in ["classic", "live", "safe"] {
+            assert_eq!(by_name(name).unwrap().name(), name);
+        }
+        assert!(by_name("missing").is_none());
+    }
+}
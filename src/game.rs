@@ -1,20 +1,38 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_std::future::{timeout, TimeoutError};
 use futures::future::join_all;
 
 use crate::{
-    board::{Board, QuartoError},
-    player::QuartoPlayer,
+    board::{Board, Move, QuartoError},
+    clock::{Clock, TimeControl},
+    player::{Context, QuartoPlayer},
+    record::GameRecord,
 };
 
+/// What happens when a player nominates an unavailable piece or places on an occupied square
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IllegalMovePolicy {
+    /// The player forfeits the game
+    #[default]
+    Forfeit,
+    /// The player is told why the move was rejected and asked again, up to this many times for
+    /// each nomination and placement, so `Retry(n)` forfeits on the illegal move after `n` retries
+    Retry(usize),
+}
+
 pub struct Game {
     board: Board,
     players: [Box<dyn QuartoPlayer>; 2],
     next: u8,
+    policy: IllegalMovePolicy,
+    clocks: [Clock; 2],
+    record: GameRecord,
 }
 
 impl Game {
+    /// Time allowed to connect and disconnect, and for each action under the default
+    /// [`TimeControl`]
     pub const CONN_TIMEOUT: Duration = Duration::from_secs(5);
 
     pub fn new(p1: impl QuartoPlayer, p2: impl QuartoPlayer) -> Self {
@@ -22,18 +40,88 @@ impl Game {
             players: [Box::new(p1), Box::new(p2)],
             board: Board::new(),
             next: 0b10,
+            policy: IllegalMovePolicy::default(),
+            clocks: [Clock::new(TimeControl::default()); 2],
+            record: GameRecord::new(Board::new()),
         }
     }
 
-    pub async fn run(&mut self) -> Result<GameResult, GameError> {
-        pollster::block_on(self.connect())?;
+    pub fn with_policy(mut self, policy: IllegalMovePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Starts from the board rather than an empty one
+    ///
+    /// The player to move follows from the pieces placed as though the game had been played
+    /// from the start, seat 1 nominating when an even number are placed and seat 0 otherwise,
+    /// with the other seat placing a nominated piece
+    pub fn with_start(mut self, board: Board) -> Self {
+        self.board = board;
+        self.record = GameRecord::new(board);
+        self.next = 1 << (1 - board.placed_count() % 2);
+        self
+    }
+
+    /// Seat of the player to move on the starting board, nominating or placing the nominated
+    /// piece, set after [`Game::with_start`]
+    pub fn with_to_move(mut self, seat: usize) -> Self {
+        let nominator = match self.board.nominated_index() {
+            Some(_) => 1 - seat,
+            None => seat,
+        };
+        self.next = 1 << nominator;
+        self
+    }
+
+    /// Seat of the player to move, nominating or placing the nominated piece
+    #[inline]
+    pub fn to_move(&self) -> usize {
+        match self.board.nominated_index() {
+            Some(_) => self.placer(),
+            None => self.nominator(),
+        }
+    }
+
+    pub fn with_time_control(mut self, control: TimeControl) -> Self {
+        self.clocks = [Clock::new(control); 2];
+        self
+    }
+
+    /// Gives the seat its own time control, set after [`Game::with_time_control`]
+    pub fn with_seat_time_control(mut self, seat: usize, control: TimeControl) -> Self {
+        self.clocks[seat] = Clock::new(control);
+        self
+    }
+
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Time the player has left
+    #[inline]
+    pub fn clock(&self, player: usize) -> Clock {
+        self.clocks[player]
+    }
+
+    /// Actions made so far, complete once the game has finished
+    #[inline]
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    pub async fn run(&mut self) -> Result<(GameResult, GameRecord), GameError> {
+        self.connect().await?;
+        for (i, player) in self.players.iter_mut().enumerate() {
+            self.record.players[i] = player.identifier().await.to_string();
+        }
 
         loop {
-            match pollster::block_on(self.next_turn())? {
+            match self.next_turn().await? {
                 GameState::Finished(res) => {
-                    println!("{:?} {:?}", self.board, res);
-                    pollster::block_on(self.disconnect())?;
-                    return Ok(res)
+                    self.disconnect().await?;
+                    return Ok((res, self.record.clone()));
                 }
                 GameState::Continue => (),
             }
@@ -62,16 +150,131 @@ impl Game {
         self.next.trailing_ones() as usize
     }
 
-    pub async fn next_turn(&mut self) -> Result<GameState, GameError> {
+    /// Applies the policy to an illegal move, returning the result if the player forfeits
+    async fn reject(
+        &mut self,
+        player: usize,
+        error: QuartoError,
+        attempts: &mut usize,
+    ) -> Option<GameResult> {
+        *attempts += 1;
+        match self.policy {
+            IllegalMovePolicy::Retry(retries) if *attempts <= retries => {
+                self.players[player].rejected(error).await;
+                None
+            }
+            _ => Some(GameResult::Forfeit(player, error)),
+        }
+    }
+
+    /// Ends the game once the player's flag falls
+    fn flag_fall(&mut self, player: usize) -> GameResult {
+        self.clocks[player].remaining = Duration::ZERO;
+        GameResult::OutOfTime(player)
+    }
+
+    /// Asks the nominator for a piece, returning the result if the game ends instead
+    async fn take_nomination(&mut self) -> Option<GameResult> {
         let n_id = self.nominator();
-        let nominator = self.players.get_mut(n_id).unwrap();
-        let nominated_piece = timeout(Game::CONN_TIMEOUT, nominator.nominate(&self.board)).await?;
-        self.board.nominate_inplace(nominated_piece)?;
+        let mut attempts = 0;
+        let instant = Instant::now();
+        loop {
+            // Time spent on rejected attempts comes off the clock
+            let mut clock = self.clocks[n_id];
+            clock.remaining = clock.remaining.saturating_sub(instant.elapsed());
+            let context = Context {
+                board: &self.board,
+                seat: n_id,
+                turn: self.board.placed_count() + 1,
+                clock,
+                opponent_clock: self.clocks[1 - n_id],
+                opponent: &self.record.players[1 - n_id],
+                history: &self.record,
+            };
+            let nominator = self.players.get_mut(n_id).unwrap();
+            let nominated_piece = timeout(clock.remaining, nominator.nominate_in(&context)).await;
+            let Ok(nominated_piece) = nominated_piece else {
+                return Some(self.flag_fall(n_id));
+            };
+            match self.board.nominate_inplace(nominated_piece) {
+                Ok(()) => {
+                    let m = Move::Nominate(nominated_piece);
+                    self.record.push(n_id, m, instant.elapsed());
+                    if !self.clocks[n_id].spend(instant.elapsed()) {
+                        return Some(self.flag_fall(n_id));
+                    }
+                    break;
+                }
+                Err(e) => {
+                    if let Some(res) = self.reject(n_id, e, &mut attempts).await {
+                        return Some(res);
+                    }
+                }
+            }
+        }
+        None
+    }
 
+    /// Asks the placer where to put the nominated piece, returning the result if the game ends
+    /// instead
+    async fn take_placement(&mut self) -> Option<GameResult> {
         let p_id = self.placer();
-        let placer = self.players.get_mut(p_id).unwrap();
-        let placer_position = timeout(Game::CONN_TIMEOUT, placer.place(&self.board)).await?;
-        self.board.place_inplace(placer_position)?;
+        let mut attempts = 0;
+        let instant = Instant::now();
+        loop {
+            // Time spent on rejected attempts comes off the clock
+            let mut clock = self.clocks[p_id];
+            clock.remaining = clock.remaining.saturating_sub(instant.elapsed());
+            let context = Context {
+                board: &self.board,
+                seat: p_id,
+                turn: self.board.placed_count() + 1,
+                clock,
+                opponent_clock: self.clocks[1 - p_id],
+                opponent: &self.record.players[1 - p_id],
+                history: &self.record,
+            };
+            let placer = self.players.get_mut(p_id).unwrap();
+            let placer_position = timeout(clock.remaining, placer.place_in(&context)).await;
+            let Ok(placer_position) = placer_position else {
+                return Some(self.flag_fall(p_id));
+            };
+            match self.board.place_inplace(placer_position) {
+                Ok(()) => {
+                    let m = Move::Place(placer_position);
+                    self.record.push(p_id, m, instant.elapsed());
+                    if !self.clocks[p_id].spend(instant.elapsed()) {
+                        return Some(self.flag_fall(p_id));
+                    }
+                    break;
+                }
+                Err(e) => {
+                    if let Some(res) = self.reject(p_id, e, &mut attempts).await {
+                        return Some(res);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub async fn next_turn(&mut self) -> Result<GameState, GameError> {
+        // Only a starting board can be won or waiting on a placement when the turn begins, whoever
+        // placed last being the nominator
+        if self.board.detect_win() {
+            return Ok(GameState::Finished(GameResult::Win(self.nominator())));
+        }
+        if self.board.nominated_index().is_none() {
+            if self.board.piece_bits() == 0 {
+                return Ok(GameState::Finished(GameResult::Draw));
+            }
+            if let Some(res) = self.take_nomination().await {
+                return Ok(GameState::Finished(res));
+            }
+        }
+        if let Some(res) = self.take_placement().await {
+            return Ok(GameState::Finished(res));
+        }
 
         if self.board.detect_win() {
             return Ok(GameState::Finished(GameResult::Win(self.placer())));
@@ -89,13 +292,13 @@ impl Game {
     pub async fn disconnect(&mut self) -> Result<(), GameError> {
         timeout(
             Game::CONN_TIMEOUT,
-            join_all(self.players.iter_mut().map(|x| x.connect())),
+            join_all(self.players.iter_mut().map(|x| x.disconnect())),
         )
         .await?
         .iter()
         .all(|x| x.is_ok())
         .then(|| ())
-        .ok_or(GameError::FailedConnection)
+        .ok_or(GameError::BadDisconnect)
     }
 }
 
@@ -106,16 +309,50 @@ pub enum GameState {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameResult {
-    Win(usize),
+    Win(#[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_seat"))] usize),
     Draw,
+    /// The player lost by making an illegal move
+    Forfeit(
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_seat"))] usize,
+        QuartoError,
+    ),
+    /// The player's flag fell before they made their move
+    OutOfTime(#[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_seat"))] usize),
+}
+
+/// Seats are 0 or 1, anything else is rejected when deserializing
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_seat<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::{de::Error, Deserialize};
+
+    match usize::deserialize(deserializer)? {
+        seat @ 0..=1 => Ok(seat),
+        seat => Err(D::Error::custom(format!("seat {seat} is not 0 or 1"))),
+    }
+}
+
+impl GameResult {
+    /// Index of the winning player
+    pub fn winner(&self) -> Option<usize> {
+        match self {
+            GameResult::Win(i) => Some(*i),
+            GameResult::Draw => None,
+            GameResult::Forfeit(i, _) | GameResult::OutOfTime(i) => Some(1 - i),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameError {
-    QuartoError(QuartoError),
     FailedConnection,
     BadDisconnect,
+    /// A player took too long to connect or disconnect
     ConnectionTimeout,
 }
 
@@ -125,14 +362,23 @@ impl From<TimeoutError> for GameError {
     }
 }
 
-impl From<QuartoError> for GameError {
-    fn from(value: QuartoError) -> Self {
-        GameError::QuartoError(value)
-    }
-}
-
+#[cfg(test)]
 pub mod tests {
-    use crate::{board::Board, position::Position};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+
+    use crate::{
+        board::{Board, Move, QuartoError},
+        clock::TimeControl,
+        game::{Game, GameResult, IllegalMovePolicy},
+        player::{Context, QuartoPlayer, RandomPlayer},
+        position::Position,
+        solver::tests::endgame,
+    };
 
     pub fn play_piece(board: &mut Board, n: usize, r: usize, c: usize) {
         board.nominate_inplace(n).unwrap();
@@ -141,6 +387,346 @@ pub mod tests {
             .unwrap();
     }
 
+    /// Player making moves from a script, in order
+    #[derive(Default)]
+    pub struct ScriptedPlayer {
+        pub nominations: Vec<usize>,
+        pub placements: Vec<usize>,
+        pub rejections: Arc<Mutex<Vec<QuartoError>>>,
+    }
+
+    impl ScriptedPlayer {
+        pub fn new(nominations: &[usize], placements: &[usize]) -> Self {
+            ScriptedPlayer {
+                nominations: nominations.iter().rev().copied().collect(),
+                placements: placements.iter().rev().copied().collect(),
+                rejections: Arc::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl QuartoPlayer for ScriptedPlayer {
+        async fn connect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        async fn identifier(&mut self) -> &str {
+            "Scripted"
+        }
+
+        async fn nominate(&mut self, _board: &Board) -> usize {
+            self.nominations.pop().unwrap()
+        }
+
+        async fn place(&mut self, _board: &Board) -> Position {
+            Position::from_index(self.placements.pop().unwrap()).unwrap()
+        }
+
+        async fn disconnect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        async fn rejected(&mut self, error: QuartoError) {
+            self.rejections.lock().unwrap().push(error);
+        }
+    }
+
+    #[test]
+    pub fn starts_from_position() {
+        // Ten pieces are placed so seat 1 nominates, then seat 0 places
+        let board = endgame().to_string().parse::<Board>().unwrap();
+        let game = Game::new(RandomPlayer, RandomPlayer).with_start(board);
+        assert_eq!((game.nominator(), game.to_move()), (1, 1));
+        let mut game = game.with_to_move(0);
+        assert_eq!((game.nominator(), game.placer()), (0, 1));
+        let (_, record) = pollster::block_on(game.run()).unwrap();
+        assert_eq!(record.start, board);
+        assert_eq!(record.actions[0].player, 0);
+        assert_eq!(record.replay().unwrap().last(), Some(game.board()));
+
+        let nominated = board.nominate(2).unwrap();
+        let mut game = Game::new(RandomPlayer, RandomPlayer).with_start(nominated);
+        assert_eq!(game.to_move(), 0);
+        let (_, record) = pollster::block_on(game.run()).unwrap();
+        assert!(matches!(record.actions[0].m, Move::Place(_)));
+        assert_eq!(record.actions[0].player, 0);
+        let game = Game::new(RandomPlayer, RandomPlayer)
+            .with_start(nominated)
+            .with_to_move(1);
+        assert_eq!((game.nominator(), game.placer()), (0, 1));
+
+        // Whoever placed the fourth piece of a row has won
+        let mut won = Board::new();
+        for i in 0..4 {
+            play_piece(&mut won, i, 1, i);
+        }
+        let mut game =
+            Game::new(ScriptedPlayer::default(), ScriptedPlayer::default()).with_start(won);
+        let (res, record) = pollster::block_on(game.run()).unwrap();
+        assert!(matches!(res, GameResult::Win(1)));
+        assert!(record.actions.is_empty());
+    }
+
+    #[test]
+    pub fn illegal_move_forfeits() {
+        // First player nominates the piece they were just given
+        let mut game = Game::new(
+            ScriptedPlayer::new(&[0], &[0]),
+            ScriptedPlayer::new(&[0], &[]),
+        );
+        let (res, _) = pollster::block_on(game.run()).unwrap();
+        assert!(matches!(
+            res,
+            GameResult::Forfeit(0, QuartoError::PieceNotAvailable)
+        ));
+        assert_eq!(res.winner(), Some(1));
+    }
+
+    #[test]
+    pub fn illegal_move_forfeits_after_retries() {
+        // With two retries the first player's two illegal nominations are retried, then its
+        // third illegal placement in a row forfeits
+        let first = ScriptedPlayer::new(&[0, 0, 1], &[0, 0, 1, 0]);
+        let second = ScriptedPlayer::new(&[0, 2], &[0, 1]);
+        let rejections = first.rejections.clone();
+
+        let mut game = Game::new(first, second).with_policy(IllegalMovePolicy::Retry(2));
+        let (res, record) = pollster::block_on(game.run()).unwrap();
+        assert!(matches!(
+            res,
+            GameResult::Forfeit(0, QuartoError::OccupiedSquare)
+        ));
+        assert_eq!(record.actions.len(), 5);
+        assert_eq!(rejections.lock().unwrap().len(), 4);
+        assert_eq!(game.board().placed_count(), 2);
+    }
+
+    #[test]
+    pub fn record_replays() {
+        // Second player completes the top row with their second placement
+        let first = ScriptedPlayer::new(&[1, 3], &[0, 2]);
+        let second = ScriptedPlayer::new(&[0, 2], &[1, 3]);
+        let mut game = Game::new(first, second);
+        let (res, record) = pollster::block_on(game.run()).unwrap();
+        assert!(matches!(res, GameResult::Win(1)));
+
+        assert_eq!(record.players, ["Scripted", "Scripted"]);
+        assert_eq!(record.turns(), 4);
+        assert_eq!(record.actions[0].player, 1);
+        assert_eq!(record.actions[0].m, Move::Nominate(0));
+        assert_eq!(
+            record.actions[1].m,
+            Move::Place(Position::from_index(0usize).unwrap())
+        );
+
+        let boards = record.replay().unwrap();
+        assert_eq!(boards.len(), record.actions.len() + 1);
+        assert_eq!(boards[0], Board::new());
+        assert_eq!(boards.last(), Some(game.board()));
+        assert!(boards.last().unwrap().detect_win());
+        assert!(!boards[boards.len() - 2].detect_win());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn serde_checks_seats() {
+        use crate::record::GameRecord;
+
+        let first = ScriptedPlayer::new(&[1, 3], &[0, 2]);
+        let second = ScriptedPlayer::new(&[0, 2], &[1, 3]);
+        let (res, record) = pollster::block_on(Game::new(first, second).run()).unwrap();
+
+        let json = serde_json::to_string(&res).unwrap();
+        assert_eq!(json, r#"{"Win":1}"#);
+        assert!(matches!(
+            serde_json::from_str(&json).unwrap(),
+            GameResult::Win(1)
+        ));
+        assert!(serde_json::from_str::<GameResult>(r#"{"Win":7}"#).is_err());
+        assert!(serde_json::from_str::<GameResult>(r#"{"OutOfTime":2}"#).is_err());
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""m":"a1""#), "{json}");
+        assert_eq!(serde_json::from_str::<GameRecord>(&json).unwrap(), record);
+        let json = json.replacen(r#""player":1"#, r#""player":3"#, 1);
+        assert!(serde_json::from_str::<GameRecord>(&json).is_err());
+    }
+
+    /// Random player which waits before nominating, logging when it starts and finishes
+    struct WaitingPlayer {
+        name: &'static str,
+        wait: Option<Duration>,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl QuartoPlayer for WaitingPlayer {
+        async fn connect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        async fn identifier(&mut self) -> &str {
+            self.name
+        }
+
+        async fn nominate(&mut self, board: &Board) -> usize {
+            self.log.lock().unwrap().push(format!("{} start", self.name));
+            match self.wait {
+                Some(wait) => async_std::task::sleep(wait).await,
+                None => futures::future::pending().await,
+            }
+            self.log.lock().unwrap().push(format!("{} end", self.name));
+            RandomPlayer.nominate(board).await
+        }
+
+        async fn place(&mut self, board: &Board) -> Position {
+            RandomPlayer.place(board).await
+        }
+
+        async fn disconnect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    /// Seat, turn, opponent, actions made and opponent's time left
+    type Request = (usize, usize, String, usize, Duration);
+
+    /// Random player logging the context of each request
+    #[derive(Default)]
+    struct ContextPlayer {
+        log: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl ContextPlayer {
+        fn log(&self, context: &Context<'_>) {
+            self.log.lock().unwrap().push((
+                context.seat,
+                context.turn,
+                context.opponent.to_string(),
+                context.history.actions.len(),
+                context.opponent_clock.remaining,
+            ));
+        }
+    }
+
+    #[async_trait]
+    impl QuartoPlayer for ContextPlayer {
+        async fn connect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        async fn identifier(&mut self) -> &str {
+            "Context"
+        }
+
+        async fn nominate(&mut self, board: &Board) -> usize {
+            RandomPlayer.nominate(board).await
+        }
+
+        async fn place(&mut self, board: &Board) -> Position {
+            RandomPlayer.place(board).await
+        }
+
+        async fn disconnect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        async fn nominate_in(&mut self, context: &Context<'_>) -> usize {
+            self.log(context);
+            self.nominate(context.board).await
+        }
+
+        async fn place_in(&mut self, context: &Context<'_>) -> Position {
+            self.log(context);
+            self.place(context.board).await
+        }
+    }
+
+    #[test]
+    pub fn passes_context() {
+        let player = ContextPlayer::default();
+        let log = player.log.clone();
+        let time = Duration::from_secs(10);
+        let mut game = Game::new(RandomPlayer, player).with_time_control(TimeControl::Total {
+            time,
+            increment: Duration::ZERO,
+        });
+        let (_, record) = pollster::block_on(game.run()).unwrap();
+
+        // Seat 1 nominates first, then places the second piece and nominates the third
+        let log = log.lock().unwrap();
+        assert_eq!(log[0], (1, 1, "RandomPlayer".to_string(), 0, time));
+        assert_eq!((log[1].1, log[1].3), (2, 3));
+        for (i, (seat, turn, opponent, actions, clock)) in log.iter().enumerate() {
+            assert_eq!((*seat, opponent.as_str()), (1, "RandomPlayer"));
+            assert_eq!(*turn, actions / 2 + 1);
+            assert_eq!(record.actions[*actions].player, 1);
+            assert!(*clock <= time && (i == 0 || *clock <= log[i - 1].4));
+        }
+    }
+
+    #[test]
+    pub fn games_interleave_on_one_thread() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut games = ["a", "b"].map(|name| {
+            let player = WaitingPlayer {
+                name,
+                wait: Some(Duration::from_millis(20)),
+                log: log.clone(),
+            };
+            Game::new(player, RandomPlayer)
+        });
+        let [a, b] = &mut games;
+        let results = futures::executor::block_on(futures::future::join(a.run(), b.run()));
+        assert!(results.0.is_ok() && results.1.is_ok());
+
+        // The second game starts waiting before the first has finished
+        let log = log.lock().unwrap();
+        assert_eq!(log[..2], ["a start", "b start"]);
+    }
+
+    #[test]
+    pub fn turns_time_out() {
+        let player = WaitingPlayer {
+            name: "Silent",
+            wait: None,
+            log: Arc::default(),
+        };
+        let mut game = Game::new(player, RandomPlayer)
+            .with_time_control(TimeControl::PerMove(Duration::from_millis(100)));
+        let (result, record) = async_std::task::block_on(game.run()).unwrap();
+        assert!(matches!(result, GameResult::OutOfTime(0)));
+        assert_eq!(result.winner(), Some(1));
+        assert_eq!(record.actions.len(), 2);
+    }
+
+    #[test]
+    pub fn flag_falls() {
+        // Waiting 40ms on each nomination runs out of 50ms on the second, before any quarto
+        let waiting = || WaitingPlayer {
+            name: "Waiting",
+            wait: Some(Duration::from_millis(40)),
+            log: Arc::default(),
+        };
+        let control = |increment| TimeControl::Total {
+            time: Duration::from_millis(50),
+            increment,
+        };
+        let mut game =
+            Game::new(waiting(), RandomPlayer).with_time_control(control(Duration::ZERO));
+        let (result, record) = async_std::task::block_on(game.run()).unwrap();
+        assert!(matches!(result, GameResult::OutOfTime(0)), "{result:?}");
+        assert_eq!(record.actions.len(), 6);
+        assert_eq!(game.clock(0).remaining, Duration::ZERO);
+
+        let mut game = Game::new(waiting(), RandomPlayer)
+            .with_time_control(control(Duration::from_millis(100)));
+        let (result, _) = async_std::task::block_on(game.run()).unwrap();
+        assert!(!matches!(result, GameResult::OutOfTime(_)));
+        assert!(game.clock(0).remaining > Duration::from_millis(50));
+    }
+
     #[test]
     pub fn row() {
         let mut board = Board::new();
@@ -2,19 +2,35 @@
 #![feature(get_many_mut)]
 #![feature(async_closure)]
 
-use crate::{game::Game, minimax::MinimaxPlayer, player::RandomPlayer, runner::GameRunner};
-
 pub mod board;
+pub mod cli;
+pub mod clock;
+pub mod engine;
+pub mod evaluation;
 pub mod game;
 pub mod piece;
 pub mod player;
 pub mod position;
+pub mod record;
 pub mod runner;
+pub mod solver;
+pub mod sprt;
+pub mod mcts;
 pub mod minimax;
+pub mod network;
+pub mod notation;
+#[cfg(feature = "output")]
+pub mod output;
+pub mod process;
+pub mod symmetry;
+pub mod tournament;
+pub mod transposition;
 
 fn main() {
-    let result = pollster::block_on(
-        GameRunner::new(16, || Game::new(MinimaxPlayer, RandomPlayer)).run(),
-    );
-    println!("{:?}", result);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Err(e) = cli::run(&args) {
+        eprintln!("error: {e}");
+        eprintln!("run `quarto help` for usage");
+        std::process::exit(2);
+    }
 }
@@ -0,0 +1,444 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rayon::{
+    iter::{IntoParallelIterator, ParallelIterator},
+    ThreadPool, ThreadPoolBuilder,
+};
+
+use crate::{
+    board::{Board, Move},
+    clock::Clock,
+    evaluation::live_lines,
+    game::Game,
+    player::{Context, QuartoPlayer},
+    position::Position,
+};
+
+#[derive(Clone)]
+pub struct MctsConfig {
+    /// UCT exploration constant
+    pub exploration: f32,
+    /// Iterations per tree for each move
+    pub iterations: Option<usize>,
+    /// Time spent searching each move
+    pub time_limit: Option<Duration>,
+    /// Number of trees searched in parallel and the size of their thread pool, `None` searches
+    /// one tree per thread of the global rayon pool
+    pub threads: Option<usize>,
+    /// Seed for the trees and playouts, `None` seeds from entropy
+    pub seed: Option<u64>,
+    /// Replaces the identifier generated from the configuration
+    pub name: Option<String>,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f32::consts::SQRT_2,
+            iterations: None,
+            time_limit: Some(MctsPlayer::DEFAULT_TIME_LIMIT),
+            threads: None,
+            seed: None,
+            name: None,
+        }
+    }
+}
+
+impl MctsConfig {
+    /// Describes the configuration, e.g. `Mcts[c=1.41,time=4750ms]`
+    pub fn identifier(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let mut options = vec![format!("c={:.2}", self.exploration)];
+        if let Some(iterations) = self.iterations {
+            options.push(format!("iterations={iterations}"));
+        }
+        if let Some(time_limit) = self.time_limit {
+            options.push(format!("time={}ms", time_limit.as_millis()));
+        }
+        if let Some(seed) = self.seed {
+            options.push(format!("seed={seed}"));
+        }
+        if let Some(threads) = self.threads {
+            options.push(format!("threads={threads}"));
+        }
+        format!("Mcts[{}]", options.join(","))
+    }
+}
+
+/// Node of the search tree, nominating and placing are separate moves so each turn spans two
+/// levels of the tree
+struct Node {
+    board: Board,
+    /// Move from the parent into this node
+    m: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    visits: u32,
+    /// Total reward for the player who made `m`, a win scores 1 and a draw 0.5
+    score: f32,
+}
+
+/// Reward for the player to move once the game is over, whoever placed the winning piece is
+/// still to move as they nominate next
+fn terminal_score(board: &Board) -> Option<f32> {
+    if board.detect_win() {
+        Some(1.0)
+    } else if board.piece_bits() == 0 && board.nominated_index().is_none() {
+        Some(0.5)
+    } else {
+        None
+    }
+}
+
+/// Converts a reward for the player to move after `m` into one for the player who made `m`
+#[inline]
+fn mover_score(m: Move, score: f32) -> f32 {
+    match m {
+        Move::Place(_) => score,
+        Move::Nominate(_) => 1.0 - score,
+    }
+}
+
+struct Tree {
+    nodes: Vec<Node>,
+    rng: StdRng,
+}
+
+impl Tree {
+    fn new(board: &Board, rng: StdRng) -> Self {
+        let mut tree = Tree {
+            nodes: Vec::new(),
+            rng,
+        };
+        tree.add_node(*board, None, None);
+        tree
+    }
+
+    fn add_node(&mut self, board: Board, m: Option<Move>, parent: Option<usize>) -> usize {
+        let mut untried = match terminal_score(&board) {
+            Some(_) => Vec::new(),
+            None => board.moves(),
+        };
+        untried.shuffle(&mut self.rng);
+        self.nodes.push(Node {
+            board,
+            m,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            score: 0.0,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn search(&mut self, exploration: f32, iterations: Option<usize>, deadline: Option<Instant>) {
+        let mut i = 0;
+        loop {
+            if iterations.is_some_and(|n| i >= n) || deadline.is_some_and(|d| Instant::now() >= d)
+            {
+                break;
+            }
+            self.iterate(exploration);
+            i += 1;
+        }
+    }
+
+    fn iterate(&mut self, exploration: f32) {
+        let mut node = 0;
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select(node, exploration);
+        }
+
+        if let Some(m) = self.nodes[node].untried.pop() {
+            let board = self.nodes[node].board.play(m).unwrap();
+            let child = self.add_node(board, Some(m), Some(node));
+            self.nodes[node].children.push(child);
+            node = child;
+        }
+
+        let board = self.nodes[node].board;
+        let score = match terminal_score(&board) {
+            Some(score) => score,
+            None => self.rollout(board),
+        };
+        self.backpropagate(node, score);
+    }
+
+    /// Child with the highest upper confidence bound
+    fn select(&self, node: usize, exploration: f32) -> usize {
+        let ln_visits = (self.nodes[node].visits as f32).ln();
+        *self.nodes[node]
+            .children
+            .iter()
+            .max_by(|&&l, &&r| {
+                let uct = |i: usize| {
+                    let child = &self.nodes[i];
+                    let visits = child.visits as f32;
+                    child.score / visits + exploration * (ln_visits / visits).sqrt()
+                };
+                uct(l).total_cmp(&uct(r))
+            })
+            .unwrap()
+    }
+
+    /// Plays random moves to the end of the game, taking a quarto whenever one is available,
+    /// returning the reward for the player to move on `board`
+    fn rollout(&mut self, mut board: Board) -> f32 {
+        let mut same_player = true;
+        loop {
+            if let Some(score) = terminal_score(&board) {
+                return if same_player { score } else { 1.0 - score };
+            }
+            let m = match board.nominated_piece() {
+                Some(piece) => live_lines(&board)
+                    .find(|&(common, _)| piece.0 & common != 0)
+                    .map(|(_, square)| Move::Place(Position::from_index(square).unwrap()))
+                    .unwrap_or_else(|| *board.moves().choose(&mut self.rng).unwrap()),
+                None => *board.moves().choose(&mut self.rng).unwrap(),
+            };
+            if let Move::Nominate(_) = m {
+                same_player = !same_player;
+            }
+            board = board.play(m).unwrap();
+        }
+    }
+
+    fn backpropagate(&mut self, mut node: usize, mut score: f32) {
+        loop {
+            let n = &mut self.nodes[node];
+            n.visits += 1;
+            let Some(m) = n.m else {
+                break;
+            };
+            score = mover_score(m, score);
+            n.score += score;
+            node = n.parent.unwrap();
+        }
+    }
+
+    /// Visits of each move from the root
+    fn root_visits(&self) -> impl Iterator<Item = (Move, u32)> + '_ {
+        self.nodes[0].children.iter().map(|&i| {
+            let child = &self.nodes[i];
+            (child.m.unwrap(), child.visits)
+        })
+    }
+}
+
+/// Monte Carlo tree search with UCT selection and random playouts, searching independent trees
+/// in parallel and combining their root visit counts
+pub struct MctsPlayer {
+    config: MctsConfig,
+    identifier: String,
+    rng: StdRng,
+    pool: Option<ThreadPool>,
+    /// Clock for the next move of the current game
+    clock: Option<Clock>,
+}
+
+impl Default for MctsPlayer {
+    fn default() -> Self {
+        MctsPlayer::new()
+    }
+}
+
+impl MctsPlayer {
+    /// Time kept back from the game's timeout to return the move
+    pub const TIME_MARGIN: Duration = Duration::from_millis(250);
+    pub const DEFAULT_TIME_LIMIT: Duration = Game::CONN_TIMEOUT.saturating_sub(Self::TIME_MARGIN);
+    /// Iterations per tree when neither an iteration nor a time limit is configured
+    pub const DEFAULT_ITERATIONS: usize = 10_000;
+
+    pub fn new() -> Self {
+        MctsPlayer::from_config(MctsConfig::default())
+    }
+
+    pub fn from_config(config: MctsConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let pool = config.threads.map(|threads| {
+            ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("Failed to build search thread pool")
+        });
+        MctsPlayer {
+            identifier: config.identifier(),
+            config,
+            rng,
+            pool,
+            clock: None,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &MctsConfig {
+        &self.config
+    }
+
+    /// Changes the time spent searching each move, the identifier keeps the configured limit
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.config.time_limit = time_limit;
+    }
+
+    /// Configured time limit, shortened to fit the clock of the current game
+    fn time_limit(&self, board: &Board) -> Option<Duration> {
+        let budget = self
+            .clock
+            .map(|clock| clock.search_time(board, Self::TIME_MARGIN));
+        match (self.config.time_limit, budget) {
+            (Some(limit), Some(budget)) => Some(limit.min(budget)),
+            (limit, budget) => limit.or(budget),
+        }
+    }
+
+    /// Most visited move over all trees
+    pub fn best_move(&mut self, board: &Board) -> Move {
+        let time_limit = self.time_limit(board);
+        let iterations = match (self.config.iterations, time_limit) {
+            (None, None) => Some(Self::DEFAULT_ITERATIONS),
+            (iterations, _) => iterations,
+        };
+        let deadline = time_limit.map(|limit| Instant::now() + limit);
+        let exploration = self.config.exploration;
+
+        let trees = self
+            .config
+            .threads
+            .unwrap_or_else(rayon::current_num_threads);
+        let seeds = (0..trees).map(|_| self.rng.gen()).collect::<Vec<u64>>();
+        let search = || {
+            seeds
+                .into_par_iter()
+                .map(|seed| {
+                    let mut tree = Tree::new(board, StdRng::seed_from_u64(seed));
+                    tree.search(exploration, iterations, deadline);
+                    tree.root_visits().collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let results = match &self.pool {
+            Some(pool) => pool.install(search),
+            None => search(),
+        };
+
+        let mut visits = board
+            .moves()
+            .into_iter()
+            .map(|m| (m, 0))
+            .collect::<Vec<_>>();
+        for (m, n) in results.into_iter().flatten() {
+            visits.iter_mut().find(|x| x.0 == m).unwrap().1 += n;
+        }
+        visits.iter().max_by_key(|x| x.1).unwrap().0
+    }
+
+    pub fn nominate(&mut self, board: &Board) -> usize {
+        match self.best_move(board) {
+            Move::Nominate(piece) => piece,
+            m => unreachable!("{:?} when nominating", m),
+        }
+    }
+
+    pub fn place(&mut self, board: &Board) -> Position {
+        match self.best_move(board) {
+            Move::Place(pos) => pos,
+            m => unreachable!("{:?} when placing", m),
+        }
+    }
+}
+
+#[async_trait]
+impl QuartoPlayer for MctsPlayer {
+    async fn connect(&mut self) -> Result<(), ()> {
+        self.clock = None;
+        Ok(())
+    }
+
+    async fn identifier(&mut self) -> &str {
+        &self.identifier
+    }
+
+    async fn nominate(&mut self, board: &Board) -> usize {
+        self.nominate(board)
+    }
+
+    async fn place(&mut self, board: &Board) -> Position {
+        self.place(board)
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ()> {
+        Ok(())
+    }
+
+    async fn nominate_in(&mut self, context: &Context<'_>) -> usize {
+        self.clock = Some(context.clock);
+        self.nominate(context.board)
+    }
+
+    async fn place_in(&mut self, context: &Context<'_>) -> Position {
+        self.clock = Some(context.clock);
+        self.place(context.board)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        board::Board,
+        game::tests::play_piece,
+        mcts::{MctsConfig, MctsPlayer},
+        position::Position,
+    };
+
+    fn config(seed: u64) -> MctsConfig {
+        MctsConfig {
+            iterations: Some(2000),
+            time_limit: None,
+            threads: Some(2),
+            seed: Some(seed),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    pub fn finds_and_avoids_quartos() {
+        let mut board = Board::new();
+        play_piece(&mut board, 0, 0, 0);
+        play_piece(&mut board, 1, 0, 1);
+        play_piece(&mut board, 2, 0, 2);
+
+        let mut player = MctsPlayer::from_config(config(1));
+        assert_eq!(
+            player.place(&board.nominate(3).unwrap()),
+            Position::from_coord(0usize, 3usize).unwrap()
+        );
+        // Only pieces 12 to 15 cannot complete the row
+        assert!(player.nominate(&board) >= 12);
+    }
+
+    #[test]
+    pub fn seeded_search_is_reproducible() {
+        let mut board = Board::new();
+        play_piece(&mut board, 4, 1, 1);
+        play_piece(&mut board, 9, 2, 2);
+
+        let mut a = MctsPlayer::from_config(config(3));
+        let mut b = MctsPlayer::from_config(config(3));
+        for _ in 0..3 {
+            assert_eq!(a.nominate(&board), b.nominate(&board));
+        }
+        assert_eq!(
+            MctsConfig::identifier(&config(3)),
+            "Mcts[c=1.41,iterations=2000,seed=3,threads=2]"
+        );
+    }
+}
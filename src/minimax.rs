@@ -1,145 +1,470 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
 use async_trait::async_trait;
 use ordered_float::OrderedFloat;
-use rand::Rng;
-use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelDrainRange, ParallelIterator};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::{
+    iter::{IntoParallelRefIterator, ParallelIterator},
+    ThreadPool, ThreadPoolBuilder,
+};
+
+use crate::{
+    board::{Board, Move},
+    clock::Clock,
+    evaluation::{Classic, Evaluator},
+    game::Game,
+    player::{Context, QuartoPlayer},
+    position::Position,
+    solver::{Outcome, Solver},
+    transposition::{Bound, Entry, TranspositionTable},
+};
 
-use crate::{board::Board, player::QuartoPlayer, position::Position};
+#[derive(Clone)]
+pub struct MinimaxConfig {
+    /// Deepest iteration of the search in plies, where nominating and placing are a ply each
+    pub depth: usize,
+    /// Time spent searching each move, `None` always searches to `depth`
+    pub time_limit: Option<Duration>,
+    pub evaluator: Arc<dyn Evaluator>,
+    /// Choose randomly between equally valued moves rather than taking the first
+    pub random_ties: bool,
+    /// Seed for tie breaking, `None` seeds from entropy
+    pub seed: Option<u64>,
+    /// Size of the thread pool searching root moves, `None` uses the global rayon pool
+    pub threads: Option<usize>,
+    /// Play perfectly with the [`Solver`] once this many pieces are placed, `None` never solves
+    pub solve_from: Option<usize>,
+    /// Replaces the identifier generated from the configuration
+    pub name: Option<String>,
+}
 
-pub struct MinimaxPlayer;
+impl Default for MinimaxConfig {
+    fn default() -> Self {
+        MinimaxConfig {
+            depth: MinimaxPlayer::MAX_DEPTH,
+            time_limit: Some(MinimaxPlayer::DEFAULT_TIME_LIMIT),
+            evaluator: Arc::new(Classic),
+            random_ties: true,
+            seed: None,
+            threads: None,
+            solve_from: Some(MinimaxPlayer::DEFAULT_SOLVE_FROM),
+            name: None,
+        }
+    }
+}
+
+impl MinimaxConfig {
+    /// Describes the configuration, e.g. `MinMax[depth=32,time=4750ms,eval=classic,random,solve=8]`
+    pub fn identifier(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let mut options = vec![format!("depth={}", self.depth)];
+        if let Some(time_limit) = self.time_limit {
+            options.push(format!("time={}ms", time_limit.as_millis()));
+        }
+        options.push(format!("eval={}", self.evaluator.name()));
+        if self.random_ties {
+            options.push("random".to_string());
+        }
+        if let Some(seed) = self.seed {
+            options.push(format!("seed={seed}"));
+        }
+        if let Some(threads) = self.threads {
+            options.push(format!("threads={threads}"));
+        }
+        if let Some(solve_from) = self.solve_from {
+            options.push(format!("solve={solve_from}"));
+        }
+        format!("MinMax[{}]", options.join(","))
+    }
+}
+
+pub struct MinimaxPlayer {
+    config: MinimaxConfig,
+    identifier: String,
+    rng: StdRng,
+    pool: Option<ThreadPool>,
+    table: TranspositionTable,
+    solver: Solver,
+    /// Clock for the next move of the current game
+    clock: Option<Clock>,
+    deadline: Option<Instant>,
+    stopped: AtomicBool,
+}
+
+/// Kind of node in the search tree, maxi nodes are evaluated from the perspective of the
+/// player searching and mini nodes from the perspective of their opponent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    MaxiPlace,
+    MaxiNominate,
+    MiniPlace,
+    MiniNominate,
+}
+
+impl Node {
+    #[inline]
+    fn next(self) -> Node {
+        match self {
+            Node::MaxiPlace => Node::MaxiNominate,
+            Node::MaxiNominate => Node::MiniPlace,
+            Node::MiniPlace => Node::MiniNominate,
+            Node::MiniNominate => Node::MaxiPlace,
+        }
+    }
+
+    #[inline]
+    fn is_place(self) -> bool {
+        matches!(self, Node::MaxiPlace | Node::MiniPlace)
+    }
+
+    /// Whether the player searching chooses the move, taking the largest value of the children
+    #[inline]
+    fn is_maxi(self) -> bool {
+        matches!(self, Node::MaxiPlace | Node::MaxiNominate)
+    }
+
+    /// Mixed into the board hash, the same board can be searched as different kinds of node
+    #[inline]
+    fn key(self) -> u64 {
+        match self {
+            Node::MaxiPlace => 0,
+            Node::MaxiNominate => 0x6A09E667F3BCC908,
+            Node::MiniPlace => 0xBB67AE8584CAA73B,
+            Node::MiniNominate => 0x3C6EF372FE94F82B,
+        }
+    }
+}
+
+impl Default for MinimaxPlayer {
+    fn default() -> Self {
+        MinimaxPlayer::new()
+    }
+}
 
 impl MinimaxPlayer {
-    pub const MAX_DEPTH: usize = 5;
+    /// Deep enough to search any position to the end of the game
+    pub const MAX_DEPTH: usize = 32;
+    /// Time kept back from the game's timeout to return the move
+    pub const TIME_MARGIN: Duration = Duration::from_millis(250);
+    pub const DEFAULT_TIME_LIMIT: Duration = Game::CONN_TIMEOUT.saturating_sub(Self::TIME_MARGIN);
+    /// Endgames with 8 free squares solve in milliseconds
+    pub const DEFAULT_SOLVE_FROM: usize = 8;
 
-    pub fn maxi_place(&self, depth: usize, board: &Board) -> f32 {
-        if depth == 0 || board.detect_win(){
-            return self.evaluate_board(board);
+    pub fn new() -> Self {
+        MinimaxPlayer::from_config(MinimaxConfig::default())
+    }
+
+    pub fn from_config(config: MinimaxConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let pool = config.threads.map(|threads| {
+            ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("Failed to build search thread pool")
+        });
+        MinimaxPlayer {
+            identifier: config.identifier(),
+            config,
+            rng,
+            pool,
+            table: TranspositionTable::default(),
+            solver: Solver::new(),
+            clock: None,
+            deadline: None,
+            stopped: AtomicBool::new(false),
         }
-        board
-            .free_spaces()
-            .par_iter()
-            .map(|i| board.place(Position::from_index(*i).unwrap()).unwrap())
-            .map(|b| OrderedFloat(self.maxi_nominate(depth - 1, &b)))
-            .max()
-            .unwrap()
-            .0
     }
 
-    pub fn maxi_nominate(&self, depth: usize, board: &Board) -> f32 {
-        if depth == 0 || board.detect_win(){
-            return self.evaluate_board(board);
+    #[inline]
+    pub fn config(&self) -> &MinimaxConfig {
+        &self.config
+    }
+
+    /// Changes the time spent searching each move, the identifier keeps the configured limit
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.config.time_limit = time_limit;
+    }
+
+    /// Configured time limit, shortened to fit the clock of the current game
+    fn time_limit(&self, board: &Board) -> Option<Duration> {
+        let budget = self
+            .clock
+            .map(|clock| clock.search_time(board, Self::TIME_MARGIN));
+        match (self.config.time_limit, budget) {
+            (Some(limit), Some(budget)) => Some(limit.min(budget)),
+            (limit, budget) => limit.or(budget),
         }
-        board
-            .piece_indexes()
-            .par_iter()
-            .map(|i| board.nominate(*i).unwrap())
-            .map(|b| OrderedFloat(self.mini_place(depth - 1, &b)))
-            .min()
-            .unwrap()
-            .0
     }
 
-    pub fn mini_place(&self, depth: usize, board: &Board) -> f32 {
-        if depth == 0 || board.detect_win(){
-            return -self.evaluate_board(board);
+    /// Whether the current search has passed its deadline, once true every thread unwinds
+    #[inline]
+    fn out_of_time(&self) -> bool {
+        if self.stopped.load(Ordering::Relaxed) {
+            return true;
         }
-        board
-            .free_spaces()
-            .par_iter()
-            .map(|i| board.place(Position::from_index(*i).unwrap()).unwrap())
-            .map(|b| OrderedFloat(self.mini_nominate(depth - 1, &b)))
-            .min()
-            .unwrap()
-            .0
+        let expired = self.deadline.is_some_and(|d| Instant::now() >= d);
+        if expired {
+            self.stopped.store(true, Ordering::Relaxed);
+        }
+        expired
+    }
+
+    /// Search ends at the depth limit, on a win or once there is nothing left to play
+    #[inline]
+    fn is_leaf(depth: usize, board: &Board) -> bool {
+        depth == 0 || board.piece_bits() == 0 || board.detect_win()
+    }
+
+    pub fn maxi_place(&self, depth: usize, board: &Board, alpha: f32, beta: f32) -> f32 {
+        self.search(Node::MaxiPlace, depth, board, alpha, beta)
     }
 
-    pub fn mini_nominate(&self, depth: usize, board: &Board) -> f32 {
-        if depth == 0 || board.detect_win(){
-            return -self.evaluate_board(board);
+    pub fn maxi_nominate(&self, depth: usize, board: &Board, alpha: f32, beta: f32) -> f32 {
+        self.search(Node::MaxiNominate, depth, board, alpha, beta)
+    }
+
+    pub fn mini_place(&self, depth: usize, board: &Board, alpha: f32, beta: f32) -> f32 {
+        self.search(Node::MiniPlace, depth, board, alpha, beta)
+    }
+
+    pub fn mini_nominate(&self, depth: usize, board: &Board, alpha: f32, beta: f32) -> f32 {
+        self.search(Node::MiniNominate, depth, board, alpha, beta)
+    }
+
+    /// Fail soft alpha-beta search, consulting the transposition table before expanding a node
+    /// and trying the best move it recorded first
+    ///
+    /// Once out of time the returned value is meaningless and nothing more is stored
+    fn search(
+        &self,
+        node: Node,
+        depth: usize,
+        board: &Board,
+        mut alpha: f32,
+        mut beta: f32,
+    ) -> f32 {
+        if Self::is_leaf(depth, board) {
+            let eval = self.evaluate_board(board);
+            return if node.is_maxi() { eval } else { -eval };
         }
-        board
-            .piece_indexes()
-            .par_iter()
-            .map(|i| board.nominate(*i).unwrap())
-            .map(|b| OrderedFloat(self.maxi_place(depth - 1, &b)))
-            .max()
-            .unwrap()
-            .0
+        if self.out_of_time() {
+            return 0.0;
+        }
+
+        let key = board.zobrist() ^ node.key();
+        let entry = self.table.probe(key);
+        if let Some(entry) = entry.filter(|e| e.depth as usize >= depth) {
+            match entry.bound {
+                Bound::Exact => return entry.value,
+                Bound::Lower => alpha = alpha.max(entry.value),
+                Bound::Upper => beta = beta.min(entry.value),
+            }
+            if alpha >= beta {
+                return entry.value;
+            }
+        }
+        let (window_alpha, window_beta) = (alpha, beta);
+
+        let mut moves = if node.is_place() {
+            board.free_spaces()
+        } else {
+            board.piece_indexes()
+        };
+        if let Some(i) = entry
+            .and_then(|e| e.best)
+            .and_then(|best| moves.iter().position(|&m| m == best as usize))
+        {
+            moves[..=i].rotate_right(1);
+        }
+
+        let mut best = if node.is_maxi() {
+            f32::NEG_INFINITY
+        } else {
+            f32::INFINITY
+        };
+        let mut best_move = None;
+        for m in moves {
+            let child = if node.is_place() {
+                board.place(Position::from_index(m).unwrap()).unwrap()
+            } else {
+                board.nominate(m).unwrap()
+            };
+            let value = self.search(node.next(), depth - 1, &child, alpha, beta);
+            if self.stopped.load(Ordering::Relaxed) {
+                return best;
+            }
+            if node.is_maxi() {
+                if best_move.is_none() || value > best {
+                    (best, best_move) = (value, Some(m));
+                }
+                alpha = alpha.max(best);
+            } else {
+                if best_move.is_none() || value < best {
+                    (best, best_move) = (value, Some(m));
+                }
+                beta = beta.min(best);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best <= window_alpha {
+            Bound::Upper
+        } else if best >= window_beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.store(
+            key,
+            Entry {
+                value: best,
+                depth: depth as u8,
+                bound,
+                best: best_move.map(|m| m as u8),
+            },
+        );
+        best
     }
 
-    pub fn nominate(&mut self, board: &Board) -> usize {
-        let moves = board
-            .piece_indexes()
-            .par_drain(..)
-            .map(|piece| {
-                let board = board.nominate(piece).unwrap();
-                (
-                    piece,
-                    OrderedFloat(self.maxi_nominate(Self::MAX_DEPTH, &board)),
-                )
-            }).collect::<Vec<_>>();
+    /// Iterative deepening over the moves available at the root, each subtree is searched in
+    /// parallel with a full window so the values of every move are exact
+    ///
+    /// `node` is the kind of node the children are, returns the values from the deepest search
+    /// completed before the deadline
+    fn root_values<M: Copy + Send + Sync>(
+        &mut self,
+        time_limit: Option<Duration>,
+        node: Node,
+        children: Vec<(M, Board)>,
+    ) -> Vec<(M, OrderedFloat<f32>)> {
+        self.deadline = time_limit.map(|limit| Instant::now() + limit);
+        self.stopped.store(false, Ordering::Relaxed);
+
+        let deepen = || {
+            // Every remaining piece is a nominate and a place
+            let plies = 2 * children[0].1.piece_indexes().len();
+            let mut completed = None;
+            for depth in 1..=self.config.depth {
+                let values = children
+                    .par_iter()
+                    .map(|(m, board)| {
+                        let value =
+                            self.search(node, depth, board, f32::NEG_INFINITY, f32::INFINITY);
+                        (*m, OrderedFloat(value))
+                    })
+                    .collect::<Vec<_>>();
+                if self.stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                completed = Some(values);
+                if depth >= plies {
+                    break;
+                }
+            }
+            completed
+        };
+        let completed = match &self.pool {
+            Some(pool) => pool.install(deepen),
+            None => deepen(),
+        };
+        self.deadline = None;
+
+        completed.unwrap_or_else(|| {
+            children
+                .iter()
+                .map(|(m, _)| (*m, OrderedFloat(0.0)))
+                .collect()
+        })
+    }
 
+    /// Picks between the moves with the best value, randomly if configured to
+    fn choose<M: Copy>(&mut self, moves: Vec<(M, OrderedFloat<f32>)>) -> M {
         let max = moves.iter().max_by_key(|x| x.1).unwrap();
         let moves = moves.iter().filter(|x| x.1 == max.1).collect::<Vec<_>>();
 
-        let mut rng = rand::thread_rng();
-        let index: usize = rng.gen::<u32>() as usize % moves.len();
+        let index = if self.config.random_ties {
+            self.rng.gen::<u32>() as usize % moves.len()
+        } else {
+            0
+        };
         let m = moves.get(index).unwrap();
         m.0
     }
 
+    /// Optimal move once the position is small enough to solve, losing positions are left to the
+    /// heuristic search in the hope the opponent goes wrong
+    fn solve(&mut self, board: &Board) -> Option<Move> {
+        let threshold = self.config.solve_from?;
+        if board.placed_count() < threshold {
+            return None;
+        }
+        let solution = self.solver.solve(board);
+        (solution.outcome != Outcome::Loss)
+            .then_some(solution.best)
+            .flatten()
+    }
+
+    pub fn nominate(&mut self, board: &Board) -> usize {
+        if let Some(Move::Nominate(piece)) = self.solve(board) {
+            return piece;
+        }
+        let children = board
+            .piece_indexes()
+            .into_iter()
+            .map(|piece| (piece, board.nominate(piece).unwrap()))
+            .collect();
+        // The opponent places the nominated piece
+        let moves = self.root_values(self.time_limit(board), Node::MiniPlace, children);
+        self.choose(moves)
+    }
+
     pub fn place(&mut self, board: &Board) -> Position {
-        let moves = board
+        if let Some(Move::Place(pos)) = self.solve(board) {
+            return pos;
+        }
+        let children = board
             .free_spaces()
-            .par_drain(..)
+            .into_iter()
             .map(|space| {
                 let pos = Position::from_index(space).unwrap();
-                let board = board.place(pos).unwrap();
-                (
-                    pos,
-                    OrderedFloat(self.maxi_nominate(Self::MAX_DEPTH, &board)),
-                )
-            }).collect::<Vec<_>>();
-
-        let max = moves.iter().max_by_key(|x| x.1).unwrap();
-        let moves = moves.iter().filter(|x| x.1 == max.1).collect::<Vec<_>>();
-
-        let mut rng = rand::thread_rng();
-        let index: usize = rng.gen::<u32>() as usize % moves.len();
-        let m = moves.get(index).unwrap();
-        m.0
+                (pos, board.place(pos).unwrap())
+            })
+            .collect();
+        let moves = self.root_values(self.time_limit(board), Node::MaxiNominate, children);
+        self.choose(moves)
     }
 
+    #[inline]
     pub fn evaluate_board(&self, board: &Board) -> f32 {
-        if board.detect_win() {
-            return f32::INFINITY;
-        }
-
-        let count = Board::quartos()
-            .par_iter()
-            .enumerate()
-            .map(|(i, xs)| {
-                let xs = xs.iter().filter_map(|&x| board.get_square_index(x));
-                let common = xs.fold(u8::MAX, |x, y| x & y.0);
-                let score = common.count_ones() as f32;
-                (i, score)
-            })
-            .max_by(|l, r| {
-                l.1.total_cmp(&r.1)
-            })
-            .unwrap();
-
-        (16 - board.placed_count()) as f32 + count.1
+        self.config.evaluator.evaluate(board)
     }
 }
 
 #[async_trait]
 impl QuartoPlayer for MinimaxPlayer {
     async fn connect(&mut self) -> Result<(), ()> {
+        // Stored values are relative to the seat this player had in the last game
+        self.table.clear();
+        self.solver.clear();
+        self.clock = None;
         Ok(())
     }
 
     async fn identifier(&mut self) -> &str {
-        "MinMax"
+        &self.identifier
     }
 
     async fn nominate(&mut self, board: &Board) -> usize {
@@ -153,4 +478,227 @@ impl QuartoPlayer for MinimaxPlayer {
     async fn disconnect(&mut self) -> Result<(), ()> {
         Ok(())
     }
+
+    async fn nominate_in(&mut self, context: &Context<'_>) -> usize {
+        self.clock = Some(context.clock);
+        self.nominate(context.board)
+    }
+
+    async fn place_in(&mut self, context: &Context<'_>) -> Position {
+        self.clock = Some(context.clock);
+        self.place(context.board)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::{Duration, Instant};
+
+    use ordered_float::OrderedFloat;
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    use crate::{
+        board::{Board, Move},
+        game::tests::play_piece,
+        minimax::{MinimaxConfig, MinimaxPlayer},
+        position::Position,
+        solver::{tests::endgame, Outcome, Solver},
+    };
+
+    /// Unpruned search over the same tree as [`MinimaxPlayer`]
+    fn full_search(
+        player: &MinimaxPlayer,
+        depth: usize,
+        board: &Board,
+        place: bool,
+        maxi: bool,
+    ) -> f32 {
+        if MinimaxPlayer::is_leaf(depth, board) {
+            let eval = player.evaluate_board(board);
+            return if maxi { eval } else { -eval };
+        }
+        let children = if place {
+            board
+                .free_spaces()
+                .into_iter()
+                .map(|i| board.place(Position::from_index(i).unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        } else {
+            board
+                .piece_indexes()
+                .into_iter()
+                .map(|i| board.nominate(i).unwrap())
+                .collect::<Vec<_>>()
+        };
+        // Place keeps the side, nominate hands the move over
+        let next_maxi = if place { maxi } else { !maxi };
+        let values = children
+            .iter()
+            .map(|b| OrderedFloat(full_search(player, depth - 1, b, !place, next_maxi)));
+        if maxi {
+            values.max().unwrap().0
+        } else {
+            values.min().unwrap().0
+        }
+    }
+
+    fn mid_game() -> Board {
+        let mut board = Board::new();
+        play_piece(&mut board, 0, 0, 0);
+        play_piece(&mut board, 5, 1, 2);
+        play_piece(&mut board, 10, 2, 1);
+        play_piece(&mut board, 15, 3, 3);
+        play_piece(&mut board, 3, 0, 3);
+        play_piece(&mut board, 12, 3, 0);
+        play_piece(&mut board, 6, 1, 1);
+        board
+    }
+
+    #[test]
+    pub fn answers_within_time_limit() {
+        let limit = Duration::from_millis(100);
+        let mut player = MinimaxPlayer::from_config(MinimaxConfig {
+            time_limit: Some(limit),
+            ..Default::default()
+        });
+        let board = Board::new().nominate(0).unwrap();
+
+        let instant = Instant::now();
+        player.place(&board);
+        assert!(
+            instant.elapsed() < limit * 3,
+            "took {:?}",
+            instant.elapsed()
+        );
+    }
+
+    #[test]
+    pub fn seeded_configuration() {
+        let config = MinimaxConfig {
+            depth: 2,
+            time_limit: None,
+            seed: Some(7),
+            threads: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.identifier(),
+            "MinMax[depth=2,eval=classic,random,seed=7,threads=2,solve=8]"
+        );
+
+        let mut a = MinimaxPlayer::from_config(config.clone());
+        let mut b = MinimaxPlayer::from_config(config);
+        let board = mid_game();
+        for _ in 0..4 {
+            assert_eq!(a.nominate(&board), b.nominate(&board));
+        }
+    }
+
+    #[test]
+    pub fn solves_endgames() {
+        let board = endgame();
+        let mut player = MinimaxPlayer::from_config(MinimaxConfig {
+            depth: 1,
+            random_ties: false,
+            ..Default::default()
+        });
+        let mut solver = Solver::new();
+
+        let mut solved = 0;
+        for piece in board.piece_indexes() {
+            let board = board.nominate(piece).unwrap();
+            let solution = solver.solve(&board);
+            if solution.outcome != Outcome::Loss {
+                assert_eq!(Some(Move::Place(player.place(&board))), solution.best);
+                solved += 1;
+            }
+        }
+        assert!(solved > 0);
+    }
+
+    #[test]
+    pub fn full_depth_matches_solver() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut player = MinimaxPlayer::from_config(MinimaxConfig {
+            depth: 32,
+            time_limit: None,
+            random_ties: false,
+            solve_from: None,
+            ..Default::default()
+        });
+        let mut solver = Solver::new();
+
+        let mut checked = 0;
+        while checked < 12 {
+            let mut board = Board::new();
+            while board.placed_count() < 9 && !board.detect_win() {
+                let piece = *board.piece_indexes().choose(&mut rng).unwrap();
+                let space = *board.free_spaces().choose(&mut rng).unwrap();
+                board = board.nominate(piece).unwrap();
+                board = board.place(Position::from_index(space).unwrap()).unwrap();
+            }
+            if board.detect_win() {
+                continue;
+            }
+
+            let piece = player.nominate(&board);
+            let outcome = -solver.solve(&board.nominate(piece).unwrap()).outcome;
+            assert_eq!(outcome, solver.solve(&board).outcome, "nominate on {board}");
+
+            let board = board.nominate(piece).unwrap();
+            let pos = player.place(&board);
+            let outcome = solver.solve(&board.place(pos).unwrap()).outcome;
+            assert_eq!(outcome, solver.solve(&board).outcome, "place on {board}");
+            checked += 1;
+        }
+    }
+
+    #[test]
+    pub fn avoids_nominating_wins() {
+        let mut board = Board::new();
+        play_piece(&mut board, 0, 0, 0);
+        play_piece(&mut board, 1, 0, 1);
+        play_piece(&mut board, 2, 0, 2);
+        let wins = |piece: usize| {
+            let board = board.nominate(piece).unwrap();
+            board.free_spaces().into_iter().any(|space| {
+                let pos = Position::from_index(space).unwrap();
+                board.place(pos).unwrap().detect_win()
+            })
+        };
+        assert!(board.piece_indexes().into_iter().any(|piece| !wins(piece)));
+
+        for depth in 1..=3 {
+            let mut player = MinimaxPlayer::from_config(MinimaxConfig {
+                depth,
+                time_limit: None,
+                seed: Some(depth as u64),
+                ..Default::default()
+            });
+            for _ in 0..8 {
+                let piece = player.nominate(&board);
+                assert!(!wins(piece), "depth {depth} nominated {piece}");
+            }
+        }
+    }
+
+    #[test]
+    pub fn alpha_beta_matches_full_search() {
+        let player = MinimaxPlayer::new();
+        let board = mid_game();
+        for depth in 1..=4 {
+            for piece in board.piece_indexes() {
+                let nominated = board.nominate(piece).unwrap();
+                for space in nominated.free_spaces() {
+                    let placed = nominated
+                        .place(Position::from_index(space).unwrap())
+                        .unwrap();
+                    let pruned =
+                        player.maxi_nominate(depth, &placed, f32::NEG_INFINITY, f32::INFINITY);
+                    let full = full_search(&player, depth, &placed, false, true);
+                    assert_eq!(pruned, full, "depth {depth} piece {piece} space {space}");
+                }
+            }
+        }
+    }
 }
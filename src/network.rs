@@ -0,0 +1,300 @@
+//! Line based protocol for playing over TCP
+//!
+//! The game connects to the engine and sends one request per line, the engine answering
+//! `connect`, `nominate`, `place` and `disconnect` with a single line. Boards, pieces and squares
+//! are written in [`crate::notation`]
+//!
+//! | Request            | Response                           |
+//! |--------------------|------------------------------------|
+//! | `connect`          | `ok <identifier>` or `error`       |
+//! | `nominate <board>` | `nominate <piece>`                 |
+//! | `place <board>`    | `place <square>`                   |
+//! | `rejected <error>` | none, the move is requested again  |
+//! | `disconnect`       | `ok` or `error`, then the engine closes the connection |
+//!
+//! Malformed requests, and requests to nominate with a piece already nominated or to place
+//! without one, are answered with `error <reason>`
+
+use async_std::{
+    io::{self, prelude::BufReadExt, BufReader, WriteExt},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    stream::StreamExt,
+    task,
+};
+use async_trait::async_trait;
+
+use crate::{
+    board::{Board, QuartoError},
+    piece::Piece,
+    player::QuartoPlayer,
+    position::Position,
+};
+
+fn parse_error(s: &str) -> Option<QuartoError> {
+    match s {
+        "NoneNominated" => Some(QuartoError::NoneNominated),
+        "PieceNotAvailable" => Some(QuartoError::PieceNotAvailable),
+        "OccupiedSquare" => Some(QuartoError::OccupiedSquare),
+        _ => None,
+    }
+}
+
+/// A player on a remote engine served by [`PlayerServer`]
+///
+/// A lost connection or malformed response leaves the move unanswered, so the player runs out of
+/// time
+pub struct TcpPlayer {
+    address: String,
+    identifier: String,
+    reader: Option<BufReader<TcpStream>>,
+    writer: Option<TcpStream>,
+}
+
+impl TcpPlayer {
+    pub fn new(address: impl Into<String>) -> Self {
+        let address = address.into();
+        TcpPlayer {
+            identifier: format!("Tcp[{address}]"),
+            address,
+            reader: None,
+            writer: None,
+        }
+    }
+
+    async fn send(&mut self, line: &str) -> io::Result<()> {
+        let writer = self.writer.as_mut().ok_or(io::ErrorKind::NotConnected)?;
+        writer.write_all(format!("{line}\n").as_bytes()).await
+    }
+
+    async fn request(&mut self, line: &str) -> io::Result<String> {
+        self.send(line).await?;
+        let reader = self.reader.as_mut().ok_or(io::ErrorKind::NotConnected)?;
+        let mut response = String::new();
+        if reader.read_line(&mut response).await? == 0 {
+            Err(io::ErrorKind::UnexpectedEof)?
+        }
+        Ok(response.trim().to_string())
+    }
+
+    /// Waits on a move which will never come, leaving the game to time out
+    async fn unanswered<T>(&mut self) -> T {
+        self.reader = None;
+        self.writer = None;
+        futures::future::pending().await
+    }
+}
+
+#[async_trait]
+impl QuartoPlayer for TcpPlayer {
+    async fn connect(&mut self) -> Result<(), ()> {
+        let stream = TcpStream::connect(&self.address).await.map_err(|_| ())?;
+        self.reader = Some(BufReader::new(stream.clone()));
+        self.writer = Some(stream);
+        let response = self.request("connect").await.map_err(|_| ())?;
+        let identifier = response.strip_prefix("ok ").ok_or(())?;
+        self.identifier = identifier.to_string();
+        Ok(())
+    }
+
+    async fn identifier(&mut self) -> &str {
+        &self.identifier
+    }
+
+    async fn nominate(&mut self, board: &Board) -> usize {
+        let response = self.request(&format!("nominate {board}")).await;
+        let piece = response.ok().and_then(|x| {
+            let piece = x.strip_prefix("nominate ")?.parse::<Piece>();
+            piece.ok()?.index()
+        });
+        match piece {
+            Some(piece) => piece,
+            None => self.unanswered().await,
+        }
+    }
+
+    async fn place(&mut self, board: &Board) -> Position {
+        let response = self.request(&format!("place {board}")).await;
+        let position = response
+            .ok()
+            .and_then(|x| x.strip_prefix("place ")?.parse().ok());
+        match position {
+            Some(position) => position,
+            None => self.unanswered().await,
+        }
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ()> {
+        let response = self.request("disconnect").await;
+        self.reader = None;
+        self.writer = None;
+        match response {
+            Ok(x) if x == "ok" => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    async fn rejected(&mut self, error: QuartoError) {
+        if self.send(&format!("rejected {error:?}")).await.is_err() {
+            self.reader = None;
+            self.writer = None;
+        }
+    }
+}
+
+/// Serves a local player over TCP, each connection playing one game against a fresh player
+pub struct PlayerServer<F> {
+    listener: TcpListener,
+    factory: F,
+}
+
+impl<F, P> PlayerServer<F>
+where
+    F: Fn() -> P,
+    P: QuartoPlayer,
+{
+    pub async fn bind(address: impl ToSocketAddrs, factory: F) -> io::Result<Self> {
+        Ok(PlayerServer {
+            listener: TcpListener::bind(address).await?,
+            factory,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts connections until the listener fails
+    pub async fn run(&self) -> io::Result<()> {
+        let mut incoming = self.listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            let player = (self.factory)();
+            task::spawn(serve(stream?, player));
+        }
+        Ok(())
+    }
+}
+
+/// Answers requests on the stream with the player until it disconnects
+pub async fn serve(stream: TcpStream, mut player: impl QuartoPlayer) -> io::Result<()> {
+    let mut writer = stream.clone();
+    let mut lines = BufReader::new(stream).lines();
+    while let Some(line) = lines.next().await {
+        let line = line?;
+        let (command, argument) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let response = match command {
+            "connect" => match player.connect().await {
+                Ok(()) => format!("ok {}", player.identifier().await),
+                Err(()) => "error".to_string(),
+            },
+            "nominate" | "place" => match argument.parse::<Board>() {
+                Ok(board) => match (command, board.nominated_index()) {
+                    ("nominate", Some(_)) => "error a piece is already nominated".to_string(),
+                    ("nominate", None) if board.piece_bits() == 0 => {
+                        "error no pieces are left to nominate".to_string()
+                    }
+                    ("nominate", None) => {
+                        let piece = player.nominate(&board).await;
+                        match Board::piece_order().get(piece) {
+                            Some(&piece) => format!("nominate {}", Piece(piece)),
+                            None => format!("error piece {piece} does not exist"),
+                        }
+                    }
+                    (_, None) => "error no piece is nominated to place".to_string(),
+                    (_, Some(_)) => format!("place {}", player.place(&board).await),
+                },
+                Err(e) => format!("error {e}"),
+            },
+            "rejected" => match parse_error(argument) {
+                Some(error) => {
+                    player.rejected(error).await;
+                    continue;
+                }
+                None => format!("error unknown error {argument}"),
+            },
+            "disconnect" => {
+                let response = match player.disconnect().await {
+                    Ok(()) => "ok",
+                    Err(()) => "error",
+                };
+                writer.write_all(format!("{response}\n").as_bytes()).await?;
+                break;
+            }
+            _ => format!("error unknown command {command}"),
+        };
+        writer.write_all(format!("{response}\n").as_bytes()).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use async_std::{
+        io::{prelude::BufReadExt, BufReader, WriteExt},
+        net::TcpStream,
+        stream::StreamExt,
+        task,
+    };
+
+    use crate::{
+        game::{Game, GameResult},
+        network::{PlayerServer, TcpPlayer},
+        player::RandomPlayer,
+    };
+
+    #[test]
+    pub fn plays_over_localhost() {
+        let server = pollster::block_on(PlayerServer::bind("127.0.0.1:0", || RandomPlayer));
+        let server = server.unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        task::spawn(async move { server.run().await });
+
+        for _ in 0..2 {
+            let mut game = Game::new(TcpPlayer::new(&address), RandomPlayer);
+            let (result, record) = pollster::block_on(game.run()).unwrap();
+            assert_eq!(record.players[0], "RandomPlayer");
+            assert_eq!(record.replay().unwrap().last(), Some(game.board()));
+            assert!(!matches!(result, GameResult::Forfeit(..)));
+        }
+    }
+
+    #[test]
+    pub fn malformed_requests() {
+        let server = pollster::block_on(PlayerServer::bind("127.0.0.1:0", || RandomPlayer));
+        let server = server.unwrap();
+        let address = server.local_addr().unwrap();
+        task::spawn(async move { server.run().await });
+
+        task::block_on(async {
+            let stream = TcpStream::connect(address).await.unwrap();
+            let mut lines = BufReader::new(stream.clone()).lines();
+            let request = |line: &str| {
+                let line = format!("{line}\n");
+                let mut stream = stream.clone();
+                async move { stream.write_all(line.as_bytes()).await.unwrap() }
+            };
+
+            request("nominate ..../..../..../.... 0123456789ABCDE -").await;
+            let response = lines.next().await.unwrap().unwrap();
+            assert_eq!(
+                response,
+                "error piece F must be either on the board or remaining, but not both"
+            );
+
+            request("resign").await;
+            let response = lines.next().await.unwrap().unwrap();
+            assert_eq!(response, "error unknown command resign");
+
+            request("place ..../..../..../.... 0123456789ABCDEF -").await;
+            let response = lines.next().await.unwrap().unwrap();
+            assert_eq!(response, "error no piece is nominated to place");
+
+            request("nominate ..../..../..../.... 0123456789ABCDEF 7").await;
+            let response = lines.next().await.unwrap().unwrap();
+            assert_eq!(response, "error a piece is already nominated");
+
+            request("place ..../..../..../.... 0123456789ABCDEF 7").await;
+            let response = lines.next().await.unwrap().unwrap();
+            assert!(response.starts_with("place "));
+        });
+    }
+}
@@ -0,0 +1,507 @@
+//! Text notation for pieces, squares, boards and games
+//!
+//! A piece is the uppercase hex digit of its index in [`Board::piece_order`], the four bits of
+//! which are its attributes. A square is its column `a` to `d` followed by its row `1` to `4`,
+//! row 1 being the top of the board.
+//!
+//! A board is three space separated fields
+//! - the rows from top to bottom separated by `/`, each square a piece or `.` when empty
+//! - the pieces not yet placed, or `-` if every piece has been
+//! - the nominated piece, or `-` if none is
+//!
+//! The starting board is `..../..../..../.... 0123456789ABCDEF -`
+//!
+//! A game is a list of turns separated by spaces, each turn being the nominated piece followed
+//! by the square it was placed on, e.g. `0a1 Fb2 7`. Games which do not start from the empty
+//! board begin with the starting board in brackets, e.g. `[0.../..../..../.... 123456789ABCDEF 5] c3 A`
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    board::{Board, Move, QuartoError},
+    piece::Piece,
+    position::Position,
+    record::GameRecord,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    InvalidPiece(char),
+    InvalidSquare(String),
+    /// A board needs its squares, remaining pieces and nominated piece
+    Fields(usize),
+    Rows(usize),
+    RowLength(usize, usize),
+    DuplicatePiece(usize),
+    /// The remaining pieces are not exactly the pieces off the board
+    Remaining(usize),
+    NominatedPlaced(usize),
+    UnclosedBoard,
+    IllegalMove(usize, Move, QuartoError),
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotationError::InvalidPiece(c) => {
+                write!(f, "'{c}' is not a piece, expected a hex digit 0-F")
+            }
+            NotationError::InvalidSquare(s) => {
+                write!(f, "'{s}' is not a square, expected a1 to d4")
+            }
+            NotationError::Fields(n) => write!(
+                f,
+                "expected 3 fields (squares, remaining pieces, nominated piece), found {n}"
+            ),
+            NotationError::Rows(n) => write!(f, "expected 4 rows separated by '/', found {n}"),
+            NotationError::RowLength(row, n) => {
+                write!(f, "expected 4 squares in row {}, found {n}", row + 1)
+            }
+            NotationError::DuplicatePiece(p) => {
+                write!(f, "piece {:X} appears more than once", p)
+            }
+            NotationError::Remaining(p) => write!(
+                f,
+                "piece {:X} must be either on the board or remaining, but not both",
+                p
+            ),
+            NotationError::NominatedPlaced(p) => {
+                write!(f, "nominated piece {:X} is already on the board", p)
+            }
+            NotationError::UnclosedBoard => write!(f, "starting board is missing its ']'"),
+            NotationError::IllegalMove(i, m, e) => {
+                write!(f, "move {} ({m}) is illegal: {e:?}", i + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+fn parse_piece(c: char) -> Result<usize, NotationError> {
+    match c {
+        '0'..='9' | 'A'..='F' => Ok(c.to_digit(16).unwrap() as usize),
+        _ => Err(NotationError::InvalidPiece(c)),
+    }
+}
+
+fn piece_char(index: usize) -> char {
+    char::from_digit(index as u32, 16)
+        .unwrap()
+        .to_ascii_uppercase()
+}
+
+impl Display for Piece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.index() {
+            Some(i) => write!(f, "{}", piece_char(i)),
+            None => write!(f, "?"),
+        }
+    }
+}
+
+impl FromStr for Piece {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Piece(Board::piece_order()[parse_piece(c)?])),
+            _ => Err(NotationError::InvalidPiece(s.chars().nth(1).unwrap_or(' '))),
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = self.to_index();
+        write!(f, "{}{}", (b'a' + (i % 4) as u8) as char, i / 4 + 1)
+    }
+}
+
+impl FromStr for Position {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || NotationError::InvalidSquare(s.to_string());
+        let mut chars = s.chars();
+        let (Some(col), Some(row), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err(invalid());
+        };
+        let col = ('a'..='d').position(|x| x == col).ok_or_else(invalid)?;
+        let row = ('1'..='4').position(|x| x == row).ok_or_else(invalid)?;
+        Ok(Position::from_coord(row, col).unwrap())
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Move::Nominate(piece) => write!(f, "{}", piece_char(*piece)),
+            Move::Place(pos) => write!(f, "{pos}"),
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Move::Nominate(parse_piece(c)?)),
+            _ => Ok(Move::Place(s.parse()?)),
+        }
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = (0..4)
+            .map(|r| {
+                (0..4)
+                    .map(|c| match self.get_square_index(r * 4 + c) {
+                        Some(piece) => piece.to_string(),
+                        None => ".".to_string(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+
+        let remaining = self.piece_indexes();
+        let remaining = match remaining.is_empty() {
+            true => "-".to_string(),
+            false => remaining.into_iter().map(piece_char).collect(),
+        };
+        let nominated = self.nominated_index().map_or('-', piece_char);
+        write!(f, "{} {} {}", rows.join("/"), remaining, nominated)
+    }
+}
+
+impl FromStr for Board {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let [squares, remaining, nominated] = fields[..] else {
+            return Err(NotationError::Fields(fields.len()));
+        };
+
+        let rows = squares.split('/').collect::<Vec<_>>();
+        if rows.len() != 4 {
+            return Err(NotationError::Rows(rows.len()));
+        }
+        let mut placed = Vec::new();
+        for (r, row) in rows.iter().enumerate() {
+            let squares = row.chars().collect::<Vec<_>>();
+            if squares.len() != 4 {
+                return Err(NotationError::RowLength(r, squares.len()));
+            }
+            for (c, &square) in squares.iter().enumerate() {
+                if square != '.' {
+                    placed.push((parse_piece(square)?, r * 4 + c));
+                }
+            }
+        }
+
+        let remaining = match remaining {
+            "-" => Vec::new(),
+            _ => remaining
+                .chars()
+                .map(parse_piece)
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let mut seen = 0u16;
+        for piece in placed.iter().map(|x| x.0).chain(remaining.iter().copied()) {
+            if seen & 1 << piece != 0 {
+                return match remaining.contains(&piece) && placed.iter().any(|x| x.0 == piece) {
+                    true => Err(NotationError::Remaining(piece)),
+                    false => Err(NotationError::DuplicatePiece(piece)),
+                };
+            }
+            seen |= 1 << piece;
+        }
+        if let Some(missing) = (0..16).find(|&i| seen & 1 << i == 0) {
+            return Err(NotationError::Remaining(missing));
+        }
+
+        let mut board = Board::new();
+        for (piece, square) in placed {
+            board.nominate_inplace(piece).unwrap();
+            board
+                .place_inplace(Position::from_index(square).unwrap())
+                .unwrap();
+        }
+        let mut chars = nominated.chars();
+        match (chars.next(), chars.next()) {
+            (Some('-'), None) => (),
+            (Some(c), None) => {
+                let piece = parse_piece(c)?;
+                board
+                    .nominate_inplace(piece)
+                    .map_err(|_| NotationError::NominatedPlaced(piece))?;
+            }
+            _ => {
+                return Err(NotationError::InvalidPiece(
+                    nominated.chars().nth(1).unwrap_or(' '),
+                ))
+            }
+        }
+        Ok(board)
+    }
+}
+
+/// The moves of a game from its starting board
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MoveList {
+    pub start: Board,
+    pub moves: Vec<Move>,
+}
+
+impl MoveList {
+    /// Boards after each move, starting with the board before the first
+    pub fn replay(&self) -> Result<Vec<Board>, NotationError> {
+        let mut boards = vec![self.start];
+        for (i, &m) in self.moves.iter().enumerate() {
+            let board = boards
+                .last()
+                .unwrap()
+                .play(m)
+                .map_err(|e| NotationError::IllegalMove(i, m, e))?;
+            boards.push(board);
+        }
+        Ok(boards)
+    }
+}
+
+impl From<&GameRecord> for MoveList {
+    fn from(value: &GameRecord) -> Self {
+        MoveList {
+            start: value.start,
+            moves: value.actions.iter().map(|x| x.m).collect(),
+        }
+    }
+}
+
+impl Display for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tokens = Vec::new();
+        if self.start != Board::new() {
+            tokens.push(format!("[{}]", self.start));
+        }
+        let mut turn = String::new();
+        for m in self.moves.iter() {
+            turn.push_str(&m.to_string());
+            if let Move::Place(_) = m {
+                tokens.push(std::mem::take(&mut turn));
+            }
+        }
+        if !turn.is_empty() {
+            tokens.push(turn);
+        }
+        write!(f, "{}", tokens.join(" "))
+    }
+}
+
+impl FromStr for MoveList {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (start, turns) = match s.strip_prefix('[') {
+            Some(rest) => {
+                let (board, turns) = rest.split_once(']').ok_or(NotationError::UnclosedBoard)?;
+                (board.parse()?, turns)
+            }
+            None => (Board::new(), s),
+        };
+
+        let mut moves = Vec::new();
+        for turn in turns.split_whitespace() {
+            let mut rest = turn;
+            while !rest.is_empty() {
+                let c = rest.chars().next().unwrap();
+                let len = if c.is_ascii_lowercase() { 2 } else { 1 };
+                let end = rest.char_indices().nth(len).map_or(rest.len(), |(i, _)| i);
+                let (token, tail) = rest.split_at(end);
+                moves.push(token.parse()?);
+                rest = tail;
+            }
+        }
+
+        let list = MoveList { start, moves };
+        list.replay()?;
+        Ok(list)
+    }
+}
+
+/// Boards, pieces, positions and moves are serialized in their notation and validated when
+/// deserialized
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use std::{fmt::Display, str::FromStr};
+
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::{
+        board::{Board, Move},
+        piece::Piece,
+        position::Position,
+    };
+
+    fn deserialize_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: Display,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+
+    macro_rules! notation_serde {
+        ($($t:ty),*) => {$(
+            impl Serialize for $t {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $t {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserialize_str(deserializer)
+                }
+            }
+        )*};
+    }
+
+    notation_serde!(Board, Piece, Position, Move);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        board::{Board, Move},
+        notation::{MoveList, NotationError},
+        position::Position,
+        solver::tests::endgame,
+    };
+
+    #[test]
+    pub fn board_round_trip() {
+        let empty = Board::new();
+        assert_eq!(empty.to_string(), "..../..../..../.... 0123456789ABCDEF -");
+
+        let board = endgame();
+        let text = board.to_string();
+        assert_eq!(text, "0F6./9.3C/.5.A/1.E. 2478BD -");
+        assert_eq!(text.parse::<Board>(), Ok(board));
+
+        let nominated = board.nominate(11).unwrap();
+        assert_eq!(nominated.to_string(), "0F6./9.3C/.5.A/1.E. 2478BD B");
+        assert_eq!(nominated.to_string().parse::<Board>(), Ok(nominated));
+    }
+
+    #[test]
+    pub fn board_errors() {
+        let parse = |s: &str| s.parse::<Board>().unwrap_err();
+        assert_eq!(
+            parse("..../..../....  0123456789ABCDEF"),
+            NotationError::Fields(2)
+        );
+        assert_eq!(
+            parse("..../..../.... 0123456789ABCDEF -"),
+            NotationError::Rows(3)
+        );
+        assert_eq!(
+            parse("..../...../..../.... 0123456789ABCDEF -"),
+            NotationError::RowLength(1, 5)
+        );
+        assert_eq!(
+            parse("x.../..../..../.... 0123456789ABCDEF -"),
+            NotationError::InvalidPiece('x')
+        );
+        assert_eq!(
+            parse("0.../..../..../.... 0123456789ABCDEF -"),
+            NotationError::Remaining(0)
+        );
+        assert_eq!(
+            parse("0.../..../..../.... 123456789ABCDE -"),
+            NotationError::Remaining(15)
+        );
+        assert_eq!(
+            parse("00../..../..../.... 123456789ABCDEF -"),
+            NotationError::DuplicatePiece(0)
+        );
+        assert_eq!(
+            parse("0.../..../..../.... 123456789ABCDEF 0"),
+            NotationError::NominatedPlaced(0)
+        );
+        assert_eq!(
+            parse("0.../..../..../.... 0123456789ABCDEF -").to_string(),
+            "piece 0 must be either on the board or remaining, but not both"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn serde_uses_notation() {
+        let board = endgame().nominate(11).unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, "\"0F6./9.3C/.5.A/1.E. 2478BD B\"");
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+
+        let error = serde_json::from_str::<Board>("\"0.../..../..../.... 0123456789ABCDEF -\"");
+        assert!(error.unwrap_err().to_string().contains("piece 0 must"));
+
+        let position = serde_json::from_str::<Position>("\"c2\"").unwrap();
+        assert_eq!(position, Position::from_coord(1usize, 2usize).unwrap());
+        assert_eq!(serde_json::to_string(&position).unwrap(), "\"c2\"");
+    }
+
+    #[test]
+    pub fn move_list_round_trip() {
+        let list = "0a1 Fb2 7".parse::<MoveList>().unwrap();
+        assert_eq!(list.moves.len(), 5);
+        assert_eq!(
+            list.moves[1],
+            Move::Place(Position::from_coord(0usize, 0usize).unwrap())
+        );
+        assert_eq!(list.to_string(), "0a1 Fb2 7");
+
+        let start = endgame().nominate(2).unwrap();
+        let list = MoveList {
+            start,
+            moves: vec![
+                Move::Place("b4".parse().unwrap()),
+                Move::Nominate(4),
+                Move::Place("d4".parse().unwrap()),
+            ],
+        };
+        let text = list.to_string();
+        assert_eq!(text, "[0F6./9.3C/.5.A/1.E. 2478BD 2] b4 4d4");
+        assert_eq!(text.parse::<MoveList>(), Ok(list));
+
+        assert_eq!(
+            "0a1 0b2".parse::<MoveList>().unwrap_err().to_string(),
+            "move 3 (0) is illegal: PieceNotAvailable"
+        );
+        assert_eq!(
+            "0e1".parse::<MoveList>().unwrap_err(),
+            NotationError::InvalidSquare("e1".to_string())
+        );
+        assert_eq!(
+            "é".parse::<MoveList>().unwrap_err(),
+            NotationError::InvalidPiece('é')
+        );
+        assert_eq!(
+            "aé".parse::<MoveList>().unwrap_err(),
+            NotationError::InvalidSquare("aé".to_string())
+        );
+        assert_eq!(
+            "0a1 ÿ".parse::<MoveList>().unwrap_err(),
+            NotationError::InvalidPiece('ÿ')
+        );
+    }
+}
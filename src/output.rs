@@ -0,0 +1,301 @@
+//! Rows for games, runs and tournaments written as JSON Lines or CSV
+//!
+//! Every row type is flat so both formats hold the same columns
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{GameError, GameResult},
+    runner::{GameOutcome, RunnerResult},
+    tournament::TournamentResult,
+};
+
+/// One game, finished or failed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRow {
+    pub game: usize,
+    /// Player in seat 0, empty if the game failed
+    pub seat0: String,
+    /// Player in seat 1, who nominates first from the empty board, empty if the game failed
+    pub seat1: String,
+    /// `win`, `draw`, `forfeit` or `error`, running out of time being a forfeit
+    pub result: String,
+    /// Seat of the winner
+    pub winner: Option<usize>,
+    /// `quarto`, `board_full`, `illegal_move:<error>`, `out_of_time`, `timeout`,
+    /// `failed_connection` or `bad_disconnect`
+    pub termination: String,
+    /// Nominations and placements made
+    pub moves: usize,
+    /// Time each seat spent on its actions
+    pub seat0_ms: f64,
+    pub seat1_ms: f64,
+    /// Time both seats spent on their actions, not the time the game took
+    pub think_ms: f64,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl From<&GameOutcome> for GameRow {
+    fn from((game, outcome): &GameOutcome) -> Self {
+        match outcome {
+            Ok((result, record)) => {
+                let (kind, termination) = match result {
+                    GameResult::Win(_) => ("win", "quarto".to_string()),
+                    GameResult::Draw => ("draw", "board_full".to_string()),
+                    GameResult::Forfeit(_, e) => ("forfeit", format!("illegal_move:{e:?}")),
+                    GameResult::OutOfTime(_) => ("forfeit", "out_of_time".to_string()),
+                };
+                let times = [record.time_taken(0), record.time_taken(1)];
+                GameRow {
+                    game: *game,
+                    seat0: record.players[0].clone(),
+                    seat1: record.players[1].clone(),
+                    result: kind.to_string(),
+                    winner: result.winner(),
+                    termination,
+                    moves: record.actions.len(),
+                    seat0_ms: millis(times[0]),
+                    seat1_ms: millis(times[1]),
+                    think_ms: millis(times[0] + times[1]),
+                }
+            }
+            Err(e) => GameRow {
+                game: *game,
+                seat0: String::new(),
+                seat1: String::new(),
+                result: "error".to_string(),
+                winner: None,
+                termination: match e {
+                    GameError::FailedConnection => "failed_connection".to_string(),
+                    GameError::BadDisconnect => "bad_disconnect".to_string(),
+                    GameError::ConnectionTimeout => "timeout".to_string(),
+                },
+                moves: 0,
+                seat0_ms: 0.0,
+                seat1_ms: 0.0,
+                think_ms: 0.0,
+            },
+        }
+    }
+}
+
+/// Totals of a [`RunnerResult`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub games: usize,
+    pub seat0_wins: usize,
+    pub seat1_wins: usize,
+    pub draws: usize,
+    pub forfeits: usize,
+    pub time_losses: usize,
+    pub timeouts: usize,
+    pub connection_failures: usize,
+    pub invalid_results: usize,
+    pub failures: usize,
+    pub aborted: bool,
+    pub seconds: f32,
+}
+
+impl From<&RunnerResult> for RunSummary {
+    fn from(value: &RunnerResult) -> Self {
+        RunSummary {
+            games: value.games(),
+            seat0_wins: value.l_wins,
+            seat1_wins: value.r_wins,
+            draws: value.draws,
+            forfeits: value.forfeits,
+            time_losses: value.time_losses,
+            timeouts: value.timeouts,
+            connection_failures: value.connection_failures,
+            invalid_results: value.invalid_results,
+            failures: value.failures.len(),
+            aborted: value.aborted,
+            seconds: value.time_taken,
+        }
+    }
+}
+
+/// An entrant's place in a tournament
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandingRow {
+    pub rank: usize,
+    pub name: String,
+    pub elo: f64,
+    pub error: f64,
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub points: f64,
+}
+
+impl StandingRow {
+    /// Rows from highest to lowest rated
+    pub fn rows(result: &TournamentResult) -> Vec<StandingRow> {
+        result
+            .standings()
+            .into_iter()
+            .enumerate()
+            .map(|(rank, i)| {
+                let total = result.total(i);
+                StandingRow {
+                    rank: rank + 1,
+                    name: result.names[i].clone(),
+                    elo: result.ratings[i].elo,
+                    error: result.ratings[i].error,
+                    games: total.games(),
+                    wins: total.wins,
+                    draws: total.draws,
+                    losses: total.losses,
+                    points: total.points(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Jsonl,
+    Csv,
+}
+
+impl Format {
+    /// CSV for `.csv` files, JSON Lines otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|x| x.to_str()) {
+            Some(x) if x.eq_ignore_ascii_case("csv") => Format::Csv,
+            _ => Format::Jsonl,
+        }
+    }
+}
+
+enum Writer<W: Write> {
+    Jsonl(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+/// Writes rows one at a time, CSV starting with a header from the first row
+pub struct RowWriter<W: Write> {
+    writer: Writer<W>,
+}
+
+impl RowWriter<BufWriter<File>> {
+    /// Creates the file, choosing the format from its extension
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = BufWriter::new(File::create(path)?);
+        Ok(RowWriter::new(Format::from_path(path), file))
+    }
+}
+
+impl<W: Write> RowWriter<W> {
+    pub fn new(format: Format, writer: W) -> Self {
+        let writer = match format {
+            Format::Jsonl => Writer::Jsonl(writer),
+            Format::Csv => Writer::Csv(Box::new(csv::Writer::from_writer(writer))),
+        };
+        RowWriter { writer }
+    }
+
+    pub fn write<T: Serialize>(&mut self, row: &T) -> io::Result<()> {
+        match &mut self.writer {
+            Writer::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, row)?;
+                writeln!(writer)
+            }
+            Writer::Csv(writer) => writer.serialize(row).map_err(io::Error::other),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Writer::Jsonl(writer) => writer.flush(),
+            Writer::Csv(writer) => writer.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        game::{tests::ScriptedPlayer, Game},
+        output::{Format, GameRow, RowWriter, RunSummary, StandingRow},
+        player::RandomPlayer,
+        runner::GameRunner,
+        tournament::{Pairing, Tournament},
+    };
+
+    #[test]
+    pub fn writes_game_rows() {
+        // Seat 0 nominates the piece it was given and forfeits
+        let runner = GameRunner::new(3, || {
+            Game::new(
+                ScriptedPlayer::new(&[0], &[0]),
+                ScriptedPlayer::new(&[0], &[]),
+            )
+        });
+        let (mut jsonl, mut csv) = (Vec::new(), Vec::new());
+        let result = {
+            let mut jsonl = RowWriter::new(Format::Jsonl, &mut jsonl);
+            let mut csv = RowWriter::new(Format::Csv, &mut csv);
+            let result = pollster::block_on(runner.run_with(|game| {
+                jsonl.write(&GameRow::from(game)).unwrap();
+                csv.write(&GameRow::from(game)).unwrap();
+            }));
+            result
+        };
+
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        let rows = jsonl
+            .lines()
+            .map(|x| serde_json::from_str::<GameRow>(x).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].seat0, "Scripted");
+        assert_eq!(rows[0].result, "forfeit");
+        assert_eq!(rows[0].winner, Some(1));
+        assert_eq!(rows[0].termination, "illegal_move:PieceNotAvailable");
+        assert_eq!(rows[0].moves, 2);
+
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("game,seat0,seat1,result,winner,termination,moves,seat0_ms,seat1_ms,think_ms")
+        );
+        assert_eq!(lines.count(), 3);
+        assert_eq!(RunSummary::from(&result).seat1_wins, 3);
+    }
+
+    #[test]
+    pub fn writes_standings() {
+        let tournament = Tournament::new(Pairing::RoundRobin, 1)
+            .entrant("a", || RandomPlayer)
+            .entrant("b", || RandomPlayer);
+        let mut games = 0;
+        let result = pollster::block_on(tournament.run_with(|_| games += 1));
+        assert_eq!(games, 2);
+
+        let mut csv = Vec::new();
+        {
+            let mut writer = RowWriter::new(Format::Csv, &mut csv);
+            for row in StandingRow::rows(&result) {
+                writer.write(&row).unwrap();
+            }
+        }
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("rank,name,elo,error,games,wins,draws,losses,points\n1,"));
+        assert_eq!(csv.lines().count(), 3);
+    }
+}
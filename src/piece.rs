@@ -1,5 +1,7 @@
 use std::fmt::Debug;
 
+use crate::board::Board;
+
 #[derive(Clone, Copy)]
 pub struct Piece(pub u8);
 
@@ -8,3 +10,10 @@ impl Debug for Piece {
         write!(f, "{:b}", self.0)
     }
 }
+
+impl Piece {
+    /// Index of the piece in [`Board::piece_order`]
+    pub fn index(&self) -> Option<usize> {
+        Board::piece_order().iter().position(|&x| x == self.0)
+    }
+}
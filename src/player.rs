@@ -1,9 +1,36 @@
-use crate::{board::Board, position::Position};
+use crate::{
+    board::{Board, QuartoError},
+    clock::Clock,
+    position::Position,
+    record::GameRecord,
+};
 use rand::prelude::*;
 
 use async_std::io;
 use async_trait::async_trait;
 
+/// What a player knows about the game when asked for a move
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    pub board: &'a Board,
+    /// Seat of the player asked, the player in seat 1 nominating first
+    pub seat: usize,
+    /// Turn being played from 1, a turn being a nomination and the placement of that piece,
+    /// counting the pieces placed on the starting board
+    pub turn: usize,
+    /// Time the player has for this move
+    pub clock: Clock,
+    pub opponent_clock: Clock,
+    /// Identifier the opponent gave when the game started
+    pub opponent: &'a str,
+    /// Every action made so far
+    pub history: &'a GameRecord,
+}
+
+/// A player asked for moves by [`crate::game::Game`]
+///
+/// Players only need the board to move, those adapting to the clock or their opponent implement
+/// [`QuartoPlayer::nominate_in`] and [`QuartoPlayer::place_in`] as well
 #[async_trait]
 pub trait QuartoPlayer: 'static + Send + Sync {
     async fn connect(&mut self) -> Result<(), ()>;
@@ -11,6 +38,55 @@ pub trait QuartoPlayer: 'static + Send + Sync {
     async fn nominate(&mut self, board: &Board) -> usize;
     async fn place(&mut self, board: &Board) -> Position;
     async fn disconnect(&mut self) -> Result<(), ()>;
+
+    /// Called when the last nomination or placement was illegal and the game lets the player
+    /// try again
+    async fn rejected(&mut self, _error: QuartoError) {}
+
+    /// Nominates with the context of the game, [`QuartoPlayer::nominate`] by default
+    async fn nominate_in(&mut self, context: &Context<'_>) -> usize {
+        self.nominate(context.board).await
+    }
+
+    /// Places with the context of the game, [`QuartoPlayer::place`] by default
+    async fn place_in(&mut self, context: &Context<'_>) -> Position {
+        self.place(context.board).await
+    }
+}
+
+#[async_trait]
+impl QuartoPlayer for Box<dyn QuartoPlayer> {
+    async fn connect(&mut self) -> Result<(), ()> {
+        self.as_mut().connect().await
+    }
+
+    async fn identifier(&mut self) -> &str {
+        self.as_mut().identifier().await
+    }
+
+    async fn nominate(&mut self, board: &Board) -> usize {
+        self.as_mut().nominate(board).await
+    }
+
+    async fn place(&mut self, board: &Board) -> Position {
+        self.as_mut().place(board).await
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ()> {
+        self.as_mut().disconnect().await
+    }
+
+    async fn rejected(&mut self, error: QuartoError) {
+        self.as_mut().rejected(error).await
+    }
+
+    async fn nominate_in(&mut self, context: &Context<'_>) -> usize {
+        self.as_mut().nominate_in(context).await
+    }
+
+    async fn place_in(&mut self, context: &Context<'_>) -> Position {
+        self.as_mut().place_in(context).await
+    }
 }
 
 pub struct CliPlayer {
@@ -57,6 +133,10 @@ impl QuartoPlayer for CliPlayer {
     async fn disconnect(&mut self) -> Result<(), ()> {
         Ok(())
     }
+
+    async fn rejected(&mut self, error: QuartoError) {
+        println!("{} {:?}, try again", self.name, error);
+    }
 }
 
 pub struct RandomPlayer;
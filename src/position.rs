@@ -2,7 +2,7 @@
 // 4  5  6  7
 // 8  9  10 11
 // 12 13 14 15
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Position(usize);
 
 impl Position {
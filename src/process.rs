@@ -0,0 +1,410 @@
+//! Text protocol for engines running as a subprocess, modelled on UCI
+//!
+//! The game writes commands to the engine's stdin and reads its answers from stdout, one per
+//! line. Boards, pieces and squares are written in [`crate::notation`]
+//!
+//! | Command                           | Answer                                          |
+//! |-----------------------------------|-------------------------------------------------|
+//! | `quarto`                          | `id name <name>`, any `option name <option> ...` lines, then `quartook` |
+//! | `setoption name <option> value <value>` | none                                      |
+//! | `isready`                         | `readyok` once the engine is ready to play      |
+//! | `position <board>`                | none, sets the board for the next request       |
+//! | `nominate <clocks>`               | `nominate <piece>` before the engine's time runs out |
+//! | `place <clocks>`                  | `place <square>` before the engine's time runs out |
+//! | `rejected <error>`                | none, the move is requested again               |
+//! | `quit`                            | none, the engine exits                          |
+//!
+//! The clocks under the game's [`crate::clock::TimeControl`] are given in milliseconds as
+//! `time <ms> inc <ms> otime <ms> oinc <ms> [movetime <ms>] budget <ms>`
+//! - `time` and `inc` are the engine's time left and the time it gains after each action
+//! - `otime` and `oinc` are the same for its opponent
+//! - `movetime` is only given under a per-move control, where the engine has `time` for every
+//!   action
+//! - `budget` is a hint at how long to spend on this action, which the engine may exceed as long
+//!   as `time` allows
+//!
+//! Engines may write `info <text>` lines at any time, which are ignored along with any other
+//! unexpected line
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver},
+    StreamExt,
+};
+
+use crate::{
+    board::{Board, QuartoError},
+    clock::{Clock, TimeControl},
+    piece::Piece,
+    player::{Context, QuartoPlayer},
+    position::Position,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ProcessConfig {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Sent with `setoption` after the handshake
+    pub options: Vec<(String, String)>,
+    /// Replaces the name the engine gives itself
+    pub name: Option<String>,
+}
+
+/// A player backed by an engine executable speaking the [`crate::process`] protocol
+///
+/// The engine is started on connecting and asked to quit on disconnecting. An engine which
+/// exits or answers out of turn leaves the move unanswered so the player runs out of time, while
+/// a failed handshake fails the connection
+pub struct ProcessPlayer {
+    config: ProcessConfig,
+    identifier: String,
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    stdout: Option<UnboundedReceiver<String>>,
+    stderr: Arc<Mutex<Vec<String>>>,
+    /// The engine's clock and its opponent's for the next request of the current game
+    clocks: Option<[Clock; 2]>,
+}
+
+impl ProcessPlayer {
+    /// Time given to the engine to exit after `quit` before it is killed
+    pub const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+    /// Lines of stderr kept for debugging
+    pub const STDERR_LINES: usize = 1000;
+
+    pub fn new(program: impl Into<String>) -> Self {
+        ProcessPlayer::from_config(ProcessConfig {
+            program: program.into(),
+            ..Default::default()
+        })
+    }
+
+    pub fn from_config(config: ProcessConfig) -> Self {
+        let identifier = match &config.name {
+            Some(name) => name.clone(),
+            None => Path::new(&config.program)
+                .file_name()
+                .map_or(config.program.clone(), |x| x.to_string_lossy().to_string()),
+        };
+        ProcessPlayer {
+            config,
+            identifier,
+            child: None,
+            stdin: None,
+            stdout: None,
+            stderr: Arc::new(Mutex::new(Vec::new())),
+            clocks: None,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &ProcessConfig {
+        &self.config
+    }
+
+    /// Most recent lines the engine wrote to stderr, including those of earlier games
+    pub fn stderr(&self) -> Vec<String> {
+        self.stderr.lock().unwrap().clone()
+    }
+
+    fn spawn(&mut self) -> std::io::Result<()> {
+        let mut child = Command::new(&self.config.program)
+            .args(&self.config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let (sender, receiver) = unbounded();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                if sender.unbounded_send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stderr = BufReader::new(child.stderr.take().unwrap());
+        let lines = self.stderr.clone();
+        thread::spawn(move || {
+            for line in stderr.lines().map_while(Result::ok) {
+                let mut lines = lines.lock().unwrap();
+                if lines.len() >= Self::STDERR_LINES {
+                    lines.remove(0);
+                }
+                lines.push(line);
+            }
+        });
+
+        self.stdin = child.stdin.take();
+        self.stdout = Some(receiver);
+        self.child = Some(child);
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), ()> {
+        let stdin = self.stdin.as_mut().ok_or(())?;
+        writeln!(stdin, "{command}")
+            .and_then(|_| stdin.flush())
+            .map_err(|_| ())
+    }
+
+    /// Reads lines until one starts with `prefix`, returning the rest of it
+    async fn expect(&mut self, prefix: &str) -> Result<String, ()> {
+        let stdout = self.stdout.as_mut().ok_or(())?;
+        while let Some(line) = stdout.next().await {
+            if let Some(rest) = line.trim().strip_prefix(prefix) {
+                return Ok(rest.trim().to_string());
+            }
+        }
+        Err(())
+    }
+
+    async fn handshake(&mut self) -> Result<(), ()> {
+        self.send("quarto")?;
+        let stdout = self.stdout.as_mut().ok_or(())?;
+        loop {
+            let line = stdout.next().await.ok_or(())?;
+            match line.trim() {
+                "quartook" => break,
+                line => {
+                    if let (Some(name), None) = (line.strip_prefix("id name "), &self.config.name) {
+                        self.identifier = name.trim().to_string();
+                    }
+                }
+            }
+        }
+        for (name, value) in self.config.options.clone() {
+            self.send(&format!("setoption name {name} value {value}"))?;
+        }
+        self.send("isready")?;
+        self.expect("readyok").await?;
+        Ok(())
+    }
+
+    /// Asks the engine for a move on the board, `None` if it cannot answer
+    async fn request(&mut self, board: &Board, command: &str) -> Option<String> {
+        self.send(&format!("position {board}")).ok()?;
+        let [clock, opponent] = self
+            .clocks
+            .unwrap_or([Clock::new(TimeControl::default()); 2]);
+        let ms = |duration: Duration| duration.as_millis();
+        let mut request = format!(
+            "{command} time {} inc {} otime {} oinc {}",
+            ms(clock.remaining),
+            ms(clock.increment()),
+            ms(opponent.remaining),
+            ms(opponent.increment())
+        );
+        if let TimeControl::PerMove(time) = clock.control {
+            request.push_str(&format!(" movetime {}", ms(time)));
+        }
+        request.push_str(&format!(" budget {}", ms(clock.budget(board))));
+        self.send(&request).ok()?;
+        self.expect(&format!("{command} ")).await.ok()
+    }
+
+    /// Waits on a move which will never come, leaving the game to time out
+    async fn unanswered<T>() -> T {
+        futures::future::pending().await
+    }
+
+    fn kill(&mut self) {
+        self.stdin = None;
+        self.stdout = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[async_trait]
+impl QuartoPlayer for ProcessPlayer {
+    async fn connect(&mut self) -> Result<(), ()> {
+        self.kill();
+        self.clocks = None;
+        self.spawn().map_err(|_| ())?;
+        let result = self.handshake().await;
+        if result.is_err() {
+            self.kill();
+        }
+        result
+    }
+
+    async fn identifier(&mut self) -> &str {
+        &self.identifier
+    }
+
+    async fn nominate(&mut self, board: &Board) -> usize {
+        let answer = self.request(board, "nominate").await;
+        match answer.and_then(|x| x.parse::<Piece>().ok()?.index()) {
+            Some(piece) => piece,
+            None => Self::unanswered().await,
+        }
+    }
+
+    async fn place(&mut self, board: &Board) -> Position {
+        let answer = self.request(board, "place").await;
+        match answer.and_then(|x| x.parse().ok()) {
+            Some(position) => position,
+            None => Self::unanswered().await,
+        }
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ()> {
+        let sent = self.send("quit");
+        self.stdin = None;
+        let Some(child) = self.child.as_mut() else {
+            return Err(());
+        };
+        let start = Instant::now();
+        let exited = loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break true,
+                Ok(None) if start.elapsed() < Self::QUIT_TIMEOUT => {
+                    async_std::task::sleep(Duration::from_millis(10)).await
+                }
+                _ => break false,
+            }
+        };
+        self.kill();
+        sent.and(exited.then_some(()).ok_or(()))
+    }
+
+    async fn rejected(&mut self, error: QuartoError) {
+        let _ = self.send(&format!("rejected {error:?}"));
+    }
+
+    async fn nominate_in(&mut self, context: &Context<'_>) -> usize {
+        self.clocks = Some([context.clock, context.opponent_clock]);
+        self.nominate(context.board).await
+    }
+
+    async fn place_in(&mut self, context: &Context<'_>) -> Position {
+        self.clocks = Some([context.clock, context.opponent_clock]);
+        self.place(context.board).await
+    }
+}
+
+impl Drop for ProcessPlayer {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        board::{Board, Move},
+        clock::{Clock, TimeControl},
+        game::{Game, GameError},
+        player::{Context, QuartoPlayer, RandomPlayer},
+        process::{ProcessConfig, ProcessPlayer},
+        record::GameRecord,
+    };
+
+    /// Engine nominating the first remaining piece and placing on the first free square
+    const FIRST_FREE: &str = r#"
+        while read -r command line; do
+            set -- $line
+            case $command in
+                quarto) echo "info starting"; echo "id name FirstFree"
+                    echo "option name Depth type spin default 1"; echo quartook ;;
+                setoption) echo "set $2=$4" >&2 ;;
+                isready) echo readyok ;;
+                position) rows=$(echo "$1" | tr -d /); remaining=$2 ;;
+                nominate) echo "nominate ${remaining%"${remaining#?}"}" ;;
+                place) echo "place $line" >&2; free=${rows%%.*}; i=${#free}
+                    echo "place $(echo abcd | cut -c $((i % 4 + 1)))$((i / 4 + 1))" ;;
+                quit) exit ;;
+            esac
+        done
+    "#;
+
+    fn first_free() -> ProcessPlayer {
+        ProcessPlayer::from_config(ProcessConfig {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), FIRST_FREE.to_string()],
+            options: vec![("Depth".to_string(), "3".to_string())],
+            name: None,
+        })
+    }
+
+    #[test]
+    pub fn plays_through_subprocess() {
+        let mut game = Game::new(first_free(), RandomPlayer);
+        let (_, record) = pollster::block_on(game.run()).unwrap();
+        assert_eq!(record.players[0], "FirstFree");
+        assert_eq!(record.actions[1].m, Move::Place("a1".parse().unwrap()));
+        assert_eq!(record.replay().unwrap().last(), Some(game.board()));
+    }
+
+    #[test]
+    pub fn captures_stderr() {
+        let mut player = first_free();
+        pollster::block_on(async {
+            player.connect().await.unwrap();
+            player.disconnect().await.unwrap();
+        });
+        assert_eq!(player.stderr(), vec!["set Depth=3"]);
+    }
+
+    #[test]
+    pub fn sends_clocks() {
+        let mut player = first_free();
+        let clock = Clock::new(TimeControl::Total {
+            time: Duration::from_secs(60),
+            increment: Duration::from_secs(1),
+        });
+        let opponent_clock = Clock {
+            remaining: Duration::from_secs(45),
+            ..clock
+        };
+        let board = Board::new().nominate(0).unwrap();
+        let history = GameRecord::new(Board::new());
+        let context = Context {
+            board: &board,
+            seat: 0,
+            turn: 1,
+            clock,
+            opponent_clock,
+            opponent: "RandomPlayer",
+            history: &history,
+        };
+        pollster::block_on(async {
+            player.connect().await.unwrap();
+            player.place_in(&context).await;
+            player.disconnect().await.unwrap();
+        });
+        // A sixteenth of the time left plus the increment
+        assert_eq!(
+            player.stderr()[1],
+            "place time 60000 inc 1000 otime 45000 oinc 1000 budget 4750"
+        );
+    }
+
+    #[test]
+    pub fn failed_handshake() {
+        let mut player = ProcessPlayer::new("sh");
+        player.config.args = vec!["-c".to_string(), "echo broken >&2".to_string()];
+        let mut game = Game::new(player, RandomPlayer);
+        let result = pollster::block_on(game.run());
+        assert!(matches!(result, Err(GameError::FailedConnection)));
+
+        let mut missing = ProcessPlayer::new("./no-such-engine");
+        assert_eq!(pollster::block_on(missing.connect()), Err(()));
+    }
+}
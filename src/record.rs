@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::board::{Board, Move, QuartoError};
+
+/// A nomination or placement made by a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Action {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::game::deserialize_seat")
+    )]
+    pub player: usize,
+    pub m: Move,
+    /// Time the player took, including any rejected attempts
+    pub time: Duration,
+}
+
+/// Every action of a game in order, enough to replay it from the starting board
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    pub players: [String; 2],
+    pub start: Board,
+    pub actions: Vec<Action>,
+}
+
+impl GameRecord {
+    pub fn new(start: Board) -> Self {
+        GameRecord {
+            players: Default::default(),
+            start,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, player: usize, m: Move, time: Duration) {
+        self.actions.push(Action { player, m, time });
+    }
+
+    /// Number of completed turns, a turn being a nomination and a placement
+    pub fn turns(&self) -> usize {
+        self.actions
+            .iter()
+            .filter(|x| matches!(x.m, Move::Place(_)))
+            .count()
+    }
+
+    /// Total time taken by the player
+    pub fn time_taken(&self, player: usize) -> Duration {
+        self.actions
+            .iter()
+            .filter(|x| x.player == player)
+            .map(|x| x.time)
+            .sum()
+    }
+
+    /// Boards after each action, starting with the board before the first
+    pub fn replay(&self) -> Result<Vec<Board>, QuartoError> {
+        let mut boards = vec![self.start];
+        for action in self.actions.iter() {
+            let board = boards.last().unwrap().play(action.m)?;
+            boards.push(board);
+        }
+        Ok(boards)
+    }
+}
@@ -1,53 +1,330 @@
-use std::time::Instant;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
 
-use futures::future::join_all;
+use futures::{channel::mpsc, SinkExt, Stream, StreamExt};
 
-use crate::game::{Game, GameResult};
+use crate::{
+    game::{Game, GameError, GameResult},
+    record::GameRecord,
+};
 
+/// Index of a game in the run and how it ended
+pub type GameOutcome = (usize, Result<(GameResult, GameRecord), GameError>);
 
+/// What the runner does once a game fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Record the failure and play the remaining games
+    #[default]
+    KeepGoing,
+    /// Stop at the first failure, games still being played are not counted
+    Abort,
+}
+
+/// Why a game could not be scored
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Failure {
+    Error(GameError),
+    /// The game finished with a winner other than either player
+    InvalidResult(GameResult),
+}
 
 pub struct GameRunner {
-    n : usize,
-    runnable : Box<dyn Fn() -> Game>,
+    n: usize,
+    runnable: Arc<dyn Fn() -> Game + Send + Sync>,
+    concurrency: usize,
+    policy: ErrorPolicy,
 }
 
 impl GameRunner {
-    pub fn new(n : usize, runnable : impl Fn() -> Game + 'static) -> Self{
+    pub fn new(n: usize, runnable: impl Fn() -> Game + Send + Sync + 'static) -> Self {
         Self {
             n,
-            runnable: Box::new(runnable),
+            runnable: Arc::new(runnable),
+            concurrency: thread::available_parallelism().map_or(1, |x| x.get()),
+            policy: ErrorPolicy::default(),
         }
     }
 
-    pub async fn run(&mut self) -> RunnerResult {
-        let instant = Instant::now();
+    pub fn with_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Most games played at once, defaults to the available parallelism
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
 
-        let mut games = (0..self.n)
-            .into_iter()
-            .map(|_| self.runnable.as_mut()())
-            .collect::<Vec<_>>();
+    /// Plays the games on a pool of worker threads, yielding each as it finishes
+    ///
+    /// Games are only created once a worker is free to play them, and workers wait for finished
+    /// games to be taken from the stream before starting more. Dropping the stream stops the
+    /// workers once their current games end
+    pub fn stream(&self) -> impl Stream<Item = GameOutcome> + Unpin {
+        let (sender, receiver) = mpsc::channel(self.concurrency);
+        let next = Arc::new(AtomicUsize::new(0));
+        for _ in 0..self.concurrency.min(self.n) {
+            let (mut sender, next) = (sender.clone(), next.clone());
+            let (n, runnable) = (self.n, self.runnable.clone());
+            thread::spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= n {
+                    break;
+                }
+                let mut game = runnable();
+                let result = pollster::block_on(game.run());
+                if pollster::block_on(sender.send((i, result))).is_err() {
+                    break;
+                }
+            });
+        }
+        receiver
+    }
 
-        let results = join_all(games.iter_mut().map(|x| x.run())).await;
-        let win_rate = results.iter().fold((0, 0, 0), |(l, r, d), new| match new {
-            Ok(GameResult::Draw) => (l, r, d + 1),
-            Ok(GameResult::Win(0)) => (l + 1, r, d ),
-            Ok(GameResult::Win(1)) => (l, r + 1, d),
-            e => panic!("{:?}", e),
-        });
+    pub async fn run(&self) -> RunnerResult {
+        self.run_with(|_| ()).await
+    }
 
-        RunnerResult {
-            time_taken: instant.elapsed().as_secs_f32(),
-            l_wins: win_rate.0,
-            r_wins: win_rate.1,
-            draws: win_rate.2,
+    /// Plays the games like [`GameRunner::run`], passing each to `on_game` as it finishes
+    pub async fn run_with(&self, mut on_game: impl FnMut(&GameOutcome)) -> RunnerResult {
+        let instant = Instant::now();
+        let mut result = RunnerResult {
+            time_taken: 0.0,
+            l_wins: 0,
+            r_wins: 0,
+            draws: 0,
+            forfeits: 0,
+            time_losses: 0,
+            timeouts: 0,
+            connection_failures: 0,
+            invalid_results: 0,
+            failures: Vec::new(),
+            aborted: false,
+        };
+
+        let mut games = self.stream();
+        while let Some(outcome) = games.next().await {
+            on_game(&outcome);
+            let (i, game) = outcome;
+            let failure = match game {
+                Ok((res, _)) => match res.winner() {
+                    None => {
+                        result.draws += 1;
+                        None
+                    }
+                    Some(winner @ (0 | 1)) => {
+                        match winner {
+                            0 => result.l_wins += 1,
+                            _ => result.r_wins += 1,
+                        }
+                        match res {
+                            GameResult::Forfeit(..) => result.forfeits += 1,
+                            GameResult::OutOfTime(_) => result.time_losses += 1,
+                            _ => (),
+                        }
+                        None
+                    }
+                    Some(_) => {
+                        result.invalid_results += 1;
+                        Some(Failure::InvalidResult(res))
+                    }
+                },
+                Err(e) => {
+                    match e {
+                        GameError::ConnectionTimeout => result.timeouts += 1,
+                        GameError::FailedConnection | GameError::BadDisconnect => {
+                            result.connection_failures += 1
+                        }
+                    }
+                    Some(Failure::Error(e))
+                }
+            };
+
+            if let Some(failure) = failure {
+                result.failures.push((i, failure));
+                if self.policy == ErrorPolicy::Abort {
+                    result.aborted = true;
+                    break;
+                }
+            }
         }
+
+        result.time_taken = instant.elapsed().as_secs_f32();
+        result
     }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RunnerResult {
     pub time_taken: f32,
     pub l_wins: usize,
     pub r_wins: usize,
     pub draws: usize,
-}
\ No newline at end of file
+    /// Games won because the other player made an illegal move, included in the wins
+    pub forfeits: usize,
+    /// Games won because the other player ran out of time, included in the wins
+    pub time_losses: usize,
+    /// Games where a player took too long to connect or disconnect
+    pub timeouts: usize,
+    /// Games where a player failed to connect or disconnect
+    pub connection_failures: usize,
+    /// Games finishing with a winner other than either player
+    pub invalid_results: usize,
+    /// Each failed game by its index in the run, in the order they finished
+    pub failures: Vec<(usize, Failure)>,
+    /// Whether the run stopped at a failure before playing every game
+    pub aborted: bool,
+}
+
+impl RunnerResult {
+    /// Games which were scored
+    #[inline]
+    pub fn games(&self) -> usize {
+        self.l_wins + self.r_wins + self.draws
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use futures::StreamExt;
+
+    use crate::{
+        board::Board,
+        game::{Game, GameError},
+        player::{QuartoPlayer, RandomPlayer},
+        position::Position,
+        runner::{ErrorPolicy, Failure, GameRunner},
+    };
+
+    /// Random player which takes a while to nominate, counting how many are nominating at once
+    struct SlowPlayer {
+        active: Arc<AtomicUsize>,
+        most: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl QuartoPlayer for SlowPlayer {
+        async fn connect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        async fn identifier(&mut self) -> &str {
+            "Slow"
+        }
+
+        async fn nominate(&mut self, board: &Board) -> usize {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(5));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            RandomPlayer.nominate(board).await
+        }
+
+        async fn place(&mut self, board: &Board) -> Position {
+            RandomPlayer.place(board).await
+        }
+
+        async fn disconnect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    /// Player which cannot connect
+    pub struct Unreachable;
+
+    #[async_trait]
+    impl QuartoPlayer for Unreachable {
+        async fn connect(&mut self) -> Result<(), ()> {
+            Err(())
+        }
+
+        async fn identifier(&mut self) -> &str {
+            "Unreachable"
+        }
+
+        async fn nominate(&mut self, board: &Board) -> usize {
+            RandomPlayer.nominate(board).await
+        }
+
+        async fn place(&mut self, board: &Board) -> Position {
+            RandomPlayer.place(board).await
+        }
+
+        async fn disconnect(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn counts_failures() {
+        // Every third game cannot connect
+        let count = Arc::new(AtomicUsize::new(0));
+        let runnable = move || match count.fetch_add(1, Ordering::SeqCst) % 3 {
+            0 => Game::new(Unreachable, RandomPlayer),
+            _ => Game::new(RandomPlayer, RandomPlayer),
+        };
+        let runner = GameRunner::new(9, runnable.clone()).with_concurrency(1);
+        let result = pollster::block_on(runner.run());
+        assert_eq!(result.games(), 6);
+        assert_eq!(result.connection_failures, 3);
+        assert_eq!(
+            result.failures.iter().map(|x| x.0).collect::<Vec<_>>(),
+            vec![0, 3, 6]
+        );
+        assert!(matches!(
+            result.failures[0].1,
+            Failure::Error(GameError::FailedConnection)
+        ));
+        assert!(!result.aborted);
+
+        let runner = GameRunner::new(9, runnable)
+            .with_concurrency(1)
+            .with_policy(ErrorPolicy::Abort);
+        let result = pollster::block_on(runner.run());
+        assert!(result.aborted);
+        assert_eq!(result.failures.len(), 1);
+        assert!(result.games() < 9);
+    }
+
+    #[test]
+    pub fn plays_concurrently() {
+        let (active, most) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let counters = (active.clone(), most.clone());
+        let runner = GameRunner::new(12, move || {
+            let player = SlowPlayer {
+                active: counters.0.clone(),
+                most: counters.1.clone(),
+            };
+            Game::new(player, RandomPlayer)
+        })
+        .with_concurrency(4);
+
+        let mut indexes = pollster::block_on(runner.stream().map(|x| x.0).collect::<Vec<_>>());
+        indexes.sort();
+        assert_eq!(indexes, (0..12).collect::<Vec<_>>());
+        assert!((2..=4).contains(&most.load(Ordering::SeqCst)));
+
+        let result = pollster::block_on(runner.run());
+        assert_eq!(result.l_wins + result.r_wins + result.draws, 12);
+        assert_eq!(active.load(Ordering::SeqCst), 0);
+    }
+}
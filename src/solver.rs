@@ -0,0 +1,241 @@
+use std::{collections::HashMap, ops::Neg};
+
+use crate::{
+    board::{Board, Move},
+    evaluation::live_lines,
+    position::Position,
+};
+
+/// Game theoretic value of a position for the player to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Neg for Outcome {
+    type Output = Outcome;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Outcome::Loss => Outcome::Win,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Win => Outcome::Loss,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Solution {
+    pub outcome: Outcome,
+    /// An optimal move, `None` once the game is over
+    pub best: Option<Move>,
+}
+
+/// Exact solver for endgames
+///
+/// The player to move places when a piece is nominated and nominates otherwise, so after
+/// placing a piece the same player moves again. Positions are solved by asking whether the
+/// player to move can force at least a draw and at least a win, which prunes far more than
+/// searching for the exact value directly
+#[derive(Default)]
+pub struct Solver {
+    cache: HashMap<(Board, Outcome), bool>,
+    nodes: usize,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver::default()
+    }
+
+    /// Positions visited since the solver was created or cleared
+    #[inline]
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.nodes = 0;
+    }
+
+    pub fn solve(&mut self, board: &Board) -> Solution {
+        if board.detect_win() {
+            // Whoever placed last made the quarto, they nominate next unless they already have
+            let outcome = match board.nominated_index() {
+                Some(_) => Outcome::Loss,
+                None => Outcome::Win,
+            };
+            return Solution {
+                outcome,
+                best: None,
+            };
+        }
+
+        let mut solution = Solution {
+            outcome: Outcome::Loss,
+            best: None,
+        };
+        for m in board.moves() {
+            let child = board.play(m).unwrap();
+            let outcome = match m {
+                Move::Place(_) if child.detect_win() => Outcome::Win,
+                Move::Place(_) => self.value(&child),
+                Move::Nominate(_) => -self.value(&child),
+            };
+            if solution.best.is_none() || outcome > solution.outcome {
+                solution = Solution {
+                    outcome,
+                    best: Some(m),
+                };
+            }
+            if outcome == Outcome::Win {
+                break;
+            }
+        }
+
+        if solution.best.is_none() {
+            // Every piece has been placed
+            solution.outcome = Outcome::Draw;
+        }
+        solution
+    }
+
+    /// Value of a position without a win on the board
+    pub fn value(&mut self, board: &Board) -> Outcome {
+        if self.at_least(board, Outcome::Win) {
+            Outcome::Win
+        } else if self.at_least(board, Outcome::Draw) {
+            Outcome::Draw
+        } else {
+            Outcome::Loss
+        }
+    }
+
+    /// Whether the player to move can force at least `target`
+    fn at_least(&mut self, board: &Board, target: Outcome) -> bool {
+        if target == Outcome::Loss {
+            return true;
+        }
+        if let Some(&result) = self.cache.get(&(*board, target)) {
+            return result;
+        }
+        self.nodes += 1;
+
+        // Attributes which complete a line when placed in its free square
+        let danger = live_lines(board).fold(0, |x, (common, _)| x | common);
+
+        let result = match board.nominated_piece() {
+            Some(piece) if piece.0 & danger != 0 => true,
+            Some(_) => board.free_spaces().into_iter().any(|i| {
+                let child = board.place(Position::from_index(i).unwrap()).unwrap();
+                self.at_least(&child, target)
+            }),
+            // Every piece placed without a quarto
+            None if board.piece_bits() == 0 => target <= Outcome::Draw,
+            None => {
+                // The opponent needs at least this to stop the target being reached
+                let refute = match target {
+                    Outcome::Win => Outcome::Draw,
+                    _ => Outcome::Win,
+                };
+                board
+                    .piece_indexes()
+                    .into_iter()
+                    .filter(|&i| Board::piece_order()[i] & danger == 0)
+                    .any(|i| !self.at_least(&board.nominate(i).unwrap(), refute))
+            }
+        };
+
+        self.cache.insert((*board, target), result);
+        result
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        board::{Board, Move},
+        game::tests::play_piece,
+        position::Position,
+        solver::{Outcome, Solver},
+    };
+
+    /// Plain negamax over every move
+    fn brute_force(board: &Board) -> Outcome {
+        board
+            .moves()
+            .into_iter()
+            .map(|m| {
+                let child = board.play(m).unwrap();
+                match m {
+                    Move::Place(_) if child.detect_win() => Outcome::Win,
+                    Move::Place(_) if child.piece_bits() == 0 => Outcome::Draw,
+                    Move::Place(_) => brute_force(&child),
+                    Move::Nominate(_) => -brute_force(&child),
+                }
+            })
+            .max()
+            .unwrap()
+    }
+
+    pub fn endgame() -> Board {
+        let mut board = Board::new();
+        let moves = [
+            (0, 0, 0),
+            (15, 0, 1),
+            (6, 0, 2),
+            (9, 1, 0),
+            (3, 1, 2),
+            (12, 1, 3),
+            (5, 2, 1),
+            (10, 2, 3),
+            (1, 3, 0),
+            (14, 3, 2),
+        ];
+        for (n, r, c) in moves {
+            play_piece(&mut board, n, r, c);
+        }
+        assert!(!board.detect_win());
+        board
+    }
+
+    #[test]
+    pub fn matches_brute_force() {
+        let board = endgame();
+        let mut solver = Solver::new();
+        for piece in board.piece_indexes() {
+            let nominated = board.nominate(piece).unwrap();
+            let solution = solver.solve(&nominated);
+            assert_eq!(solution.outcome, brute_force(&nominated), "piece {piece}");
+
+            let best = nominated.play(solution.best.unwrap()).unwrap();
+            if !best.detect_win() {
+                assert_eq!(solver.value(&best), solution.outcome);
+            }
+        }
+        assert_eq!(solver.solve(&board).outcome, brute_force(&board));
+    }
+
+    #[test]
+    pub fn finished_games() {
+        let mut board = Board::new();
+        play_piece(&mut board, 0, 0, 0);
+        play_piece(&mut board, 1, 0, 1);
+        play_piece(&mut board, 2, 0, 2);
+
+        let mut solver = Solver::new();
+        let solution = solver.solve(&board.nominate(3).unwrap());
+        assert_eq!(solution.outcome, Outcome::Win);
+        assert_eq!(
+            solution.best,
+            Some(Move::Place(Position::from_coord(0usize, 3usize).unwrap()))
+        );
+
+        play_piece(&mut board, 3, 0, 3);
+        assert_eq!(solver.solve(&board).outcome, Outcome::Win);
+        assert_eq!(solver.solve(&board).best, None);
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    board::Board,
+    clock::TimeControl,
+    game::Game,
+    player::QuartoPlayer,
+    runner::{ErrorPolicy, GameRunner, RunnerResult},
+};
+
+/// Sequential probability ratio test between two Elo hypotheses
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    /// Elo difference under the null hypothesis
+    pub elo0: f64,
+    /// Elo difference under the alternative hypothesis
+    pub elo1: f64,
+    /// Chance of accepting the alternative when the null hypothesis holds
+    pub alpha: f64,
+    /// Chance of accepting the null when the alternative hypothesis holds
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Verdict {
+    /// The candidate is no stronger than `elo0`
+    AcceptH0,
+    /// The candidate is at least `elo1` stronger
+    AcceptH1,
+    /// Neither bound was reached before the game limit
+    Inconclusive,
+}
+
+/// Expected score of a player rated `elo` above their opponent
+#[inline]
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+impl Sprt {
+    /// Log likelihood ratio bounds, below the first accepts H0 and above the second accepts H1
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log likelihood ratio of the results under a trinomial model, using the normal
+    /// approximation of the generalised SPRT
+    ///
+    /// Half a game is added to each outcome while either side has no wins, as the variance
+    /// cannot be estimated until then
+    pub fn llr(&self, wins: usize, draws: usize, losses: usize) -> f64 {
+        let prior = match wins == 0 || losses == 0 {
+            true => 0.5,
+            false => 0.0,
+        };
+        let (wins, draws, losses) = (
+            wins as f64 + prior,
+            draws as f64 + prior,
+            losses as f64 + prior,
+        );
+        let n = wins + draws + losses;
+        let score = (wins + draws / 2.0) / n;
+        let variance = (wins + draws / 4.0) / n - score * score;
+        let (s0, s1) = (expected_score(self.elo0), expected_score(self.elo1));
+        n * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, llr: f64) -> Option<Verdict> {
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Some(Verdict::AcceptH0)
+        } else if llr >= upper {
+            Some(Verdict::AcceptH1)
+        } else {
+            None
+        }
+    }
+}
+
+/// Plays a candidate against a baseline in pairs of games with the seats swapped, until the
+/// test accepts a hypothesis or the game limit is reached
+///
+/// The match also stops once every game of a batch in both seatings fails, or at the first
+/// failure under [`ErrorPolicy::Abort`]
+pub struct SprtMatch {
+    sprt: Sprt,
+    candidate: Arc<dyn Fn() -> Box<dyn QuartoPlayer> + Send + Sync>,
+    baseline: Arc<dyn Fn() -> Box<dyn QuartoPlayer> + Send + Sync>,
+    /// Pairs of games played between each test of the results
+    pub batch: usize,
+    pub max_games: usize,
+    pub time_control: TimeControl,
+    pub policy: ErrorPolicy,
+    /// Starting boards, each played once from either seat
+    openings: Arc<Vec<Board>>,
+}
+
+impl SprtMatch {
+    pub const DEFAULT_BATCH: usize = 8;
+    pub const DEFAULT_MAX_GAMES: usize = 20_000;
+
+    pub fn new<C: QuartoPlayer, B: QuartoPlayer>(
+        sprt: Sprt,
+        candidate: impl Fn() -> C + Send + Sync + 'static,
+        baseline: impl Fn() -> B + Send + Sync + 'static,
+    ) -> Self {
+        SprtMatch {
+            sprt,
+            candidate: Arc::new(move || Box::new(candidate())),
+            baseline: Arc::new(move || Box::new(baseline())),
+            batch: Self::DEFAULT_BATCH,
+            max_games: Self::DEFAULT_MAX_GAMES,
+            time_control: TimeControl::default(),
+            policy: ErrorPolicy::default(),
+            openings: Arc::default(),
+        }
+    }
+
+    pub fn with_batch(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+
+    pub fn with_max_games(mut self, max_games: usize) -> Self {
+        self.max_games = max_games;
+        self
+    }
+
+    pub fn with_time_control(mut self, time_control: TimeControl) -> Self {
+        self.time_control = time_control;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Plays the games from the boards in turn rather than the empty board
+    pub fn with_openings(mut self, openings: Vec<Board>) -> Self {
+        self.openings = Arc::new(openings);
+        self
+    }
+
+    /// Starting boards for the games of a batch, taking the openings in turn from `first`
+    fn starts(&self, first: usize) -> impl Fn() -> Board + Send + Sync + 'static {
+        let (openings, next) = (self.openings.clone(), AtomicUsize::new(first));
+        move || match openings.len() {
+            0 => Board::new(),
+            n => openings[next.fetch_add(1, Ordering::Relaxed) % n],
+        }
+    }
+
+    /// Plays a batch from the openings taken in turn from `first`, with the candidate in seat 0
+    /// or seat 1
+    async fn batch(&self, candidate_seat: usize, first: usize) -> RunnerResult {
+        let (candidate, baseline) = (self.candidate.clone(), self.baseline.clone());
+        let (start, control) = (self.starts(first), self.time_control);
+        GameRunner::new(self.batch, move || {
+            let game = match candidate_seat {
+                0 => Game::new(candidate(), baseline()),
+                _ => Game::new(baseline(), candidate()),
+            };
+            game.with_start(start()).with_time_control(control)
+        })
+        .with_policy(self.policy)
+        .run()
+        .await
+    }
+
+    pub async fn run(&self) -> SprtResult {
+        let (lower, upper) = self.sprt.bounds();
+        let mut result = SprtResult {
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            llr: 0.0,
+            lower,
+            upper,
+            verdict: Verdict::Inconclusive,
+            failures: 0,
+            aborted: false,
+        };
+
+        let mut batches = 0;
+        while result.games() + result.failures < self.max_games {
+            let mut scored = 0;
+            // Both seatings play the same openings
+            for candidate_seat in [0, 1] {
+                let games = self.batch(candidate_seat, batches * self.batch).await;
+                let (wins, losses) = match candidate_seat {
+                    0 => (games.l_wins, games.r_wins),
+                    _ => (games.r_wins, games.l_wins),
+                };
+                result.wins += wins;
+                result.draws += games.draws;
+                result.losses += losses;
+                result.failures += games.failures.len();
+                scored += games.games();
+                result.aborted |= games.aborted;
+                if games.aborted {
+                    break;
+                }
+            }
+            batches += 1;
+
+            result.llr = self.sprt.llr(result.wins, result.draws, result.losses);
+            if result.aborted || scored == 0 {
+                result.aborted = true;
+                break;
+            }
+            if let Some(verdict) = self.sprt.verdict(result.llr) {
+                result.verdict = verdict;
+                break;
+            }
+        }
+        result
+    }
+}
+
+/// Results from the candidate's perspective
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SprtResult {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub llr: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub verdict: Verdict,
+    /// Games which failed and were not scored, counted towards the game limit
+    pub failures: usize,
+    /// Whether the match stopped early because games failed
+    pub aborted: bool,
+}
+
+impl SprtResult {
+    #[inline]
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Candidate's score as a fraction of the points available, none before any game is scored
+    pub fn score(&self) -> Option<f64> {
+        let games = self.games();
+        (games > 0).then(|| (self.wins as f64 + self.draws as f64 / 2.0) / games as f64)
+    }
+
+    /// Elo difference implied by the score, none without a score or when the candidate won or
+    /// lost every game as the difference is unbounded
+    pub fn elo(&self) -> Option<f64> {
+        self.score()
+            .filter(|&score| score > 0.0 && score < 1.0)
+            .map(|score| -400.0 * (1.0 / score - 1.0).log10())
+    }
+}
+
+impl Display for SprtResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let score = self
+            .score()
+            .map_or("-".to_string(), |x| format!("{:.1}%", 100.0 * x));
+        let elo = self.elo().map_or("-".to_string(), |x| format!("{x:+.1}"));
+        write!(
+            f,
+            "W-D-L {}-{}-{} ({score}, Elo {elo}), LLR {:.2} [{:.2}, {:.2}], {:?}",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.llr,
+            self.lower,
+            self.upper,
+            self.verdict
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        minimax::{MinimaxConfig, MinimaxPlayer},
+        player::RandomPlayer,
+        runner::{tests::Unreachable, ErrorPolicy},
+        sprt::{Sprt, SprtMatch, SprtResult, Verdict},
+    };
+
+    #[test]
+    pub fn likelihood_ratio() {
+        let sprt = Sprt::default();
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 1e-3 && (upper - 2.944).abs() < 1e-3);
+
+        // Even results favour H0, strong results H1
+        assert!(sprt.llr(400, 200, 400) < 0.0);
+        assert!(sprt.llr(450, 200, 350) > upper);
+        assert_eq!(
+            sprt.verdict(sprt.llr(450, 200, 350)),
+            Some(Verdict::AcceptH1)
+        );
+        assert_eq!(sprt.verdict(sprt.llr(100, 50, 100)), None);
+        assert!(sprt.llr(10, 0, 0) > 0.0 && sprt.llr(0, 0, 10) < 0.0);
+    }
+
+    #[test]
+    pub fn stronger_candidate() {
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 100.0,
+            ..Default::default()
+        };
+        let candidate = || {
+            MinimaxPlayer::from_config(MinimaxConfig {
+                depth: 2,
+                time_limit: None,
+                ..Default::default()
+            })
+        };
+        let result = pollster::block_on(
+            SprtMatch::new(sprt, candidate, || RandomPlayer)
+                .with_batch(4)
+                .with_max_games(400)
+                .run(),
+        );
+        assert_eq!(result.verdict, Verdict::AcceptH1, "{result}");
+        assert!(result.llr >= result.upper);
+        assert!(result.games().is_multiple_of(8));
+    }
+
+    #[test]
+    pub fn stops_when_games_fail() {
+        let sprt_match = SprtMatch::new(Sprt::default(), || Unreachable, || RandomPlayer)
+            .with_batch(2)
+            .with_max_games(400);
+        let result = pollster::block_on(sprt_match.run());
+        assert!(result.aborted);
+        assert_eq!((result.games(), result.failures), (0, 4));
+        assert_eq!((result.score(), result.elo()), (None, None));
+        assert!(result.to_string().starts_with("W-D-L 0-0-0 (-, Elo -)"));
+
+        let sprt_match = sprt_match.with_policy(ErrorPolicy::Abort);
+        let result = pollster::block_on(sprt_match.run());
+        assert!(result.aborted);
+        assert_eq!(result.failures, 1);
+    }
+
+    #[test]
+    pub fn unbounded_elo() {
+        let result = SprtResult {
+            wins: 3,
+            draws: 0,
+            losses: 0,
+            llr: 0.0,
+            lower: -2.9,
+            upper: 2.9,
+            verdict: Verdict::Inconclusive,
+            failures: 0,
+            aborted: false,
+        };
+        assert_eq!((result.score(), result.elo()), (Some(1.0), None));
+        let result = SprtResult { losses: 3, ..result };
+        assert_eq!(result.score(), Some(0.5));
+        assert_eq!(result.elo(), Some(0.0));
+    }
+}
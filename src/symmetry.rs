@@ -0,0 +1,233 @@
+//! Symmetries of Quarto positions
+//!
+//! Without the quads the board has 32 symmetries, the 8 rotations and reflections combined with
+//! swapping the inner and outer rows and columns. Those swaps split up the 2x2 quads in
+//! [`Board::quartos`], so only the 8 rotations and reflections preserve all 19 lines.
+//!
+//! Pieces can also have their attributes permuted (24 ways) and complemented (16 ways), which
+//! preserves whether a line of pieces shares an attribute, giving 8 * 24 * 16 = 3072 symmetries
+
+use crate::{board::Board, position::Position};
+
+/// Maps a position to an equivalent one, applying a board symmetry and then an attribute
+/// complement and permutation to every piece
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    geometry: u8,
+    /// Attribute `i` of a piece becomes attribute `permutation[i]`
+    permutation: [u8; 4],
+    /// Attributes flipped before permuting
+    complement: u8,
+}
+
+impl Default for Symmetry {
+    fn default() -> Self {
+        Symmetry::IDENTITY
+    }
+}
+
+impl Symmetry {
+    pub const IDENTITY: Symmetry = Symmetry {
+        geometry: 0,
+        permutation: [0, 1, 2, 3],
+        complement: 0,
+    };
+
+    pub const GEOMETRIES: usize = 8;
+
+    /// All 3072 symmetries, starting with the identity
+    pub fn all() -> impl Iterator<Item = Symmetry> {
+        (0..Symmetry::GEOMETRIES as u8).flat_map(|geometry| {
+            permutations().flat_map(move |permutation| {
+                (0..16).map(move |complement| Symmetry {
+                    geometry,
+                    permutation,
+                    complement,
+                })
+            })
+        })
+    }
+
+    /// Image of a square under the rotation or reflection
+    #[inline]
+    pub fn square(&self, index: usize) -> usize {
+        let (r, c) = (index / 4, index % 4);
+        let (r, c) = match self.geometry {
+            0 => (r, c),
+            1 => (c, 3 - r),
+            2 => (3 - r, 3 - c),
+            3 => (3 - c, r),
+            4 => (r, 3 - c),
+            5 => (3 - r, c),
+            6 => (c, r),
+            _ => (3 - c, 3 - r),
+        };
+        r * 4 + c
+    }
+
+    #[inline]
+    pub fn position(&self, pos: Position) -> Position {
+        Position::from_index(self.square(pos.to_index())).unwrap()
+    }
+
+    /// Image of a piece index, the bits of an index in [`Board::piece_order`] are its attributes
+    #[inline]
+    pub fn piece(&self, index: usize) -> usize {
+        let flipped = index ^ self.complement as usize;
+        (0..4)
+            .filter(|&i| flipped & 1 << i != 0)
+            .fold(0, |x, i| x | 1 << self.permutation[i])
+    }
+
+    /// Applies the symmetry to every placed and nominated piece
+    pub fn board(&self, board: &Board) -> Board {
+        let mut out = Board::new();
+        for i in board.taken_spaces() {
+            let piece = board.get_square_index(i).unwrap().index().unwrap();
+            out.nominate_inplace(self.piece(piece)).unwrap();
+            out.place_inplace(Position::from_index(self.square(i)).unwrap())
+                .unwrap();
+        }
+        if let Some(nominated) = board.nominated_index() {
+            out.nominate_inplace(self.piece(nominated)).unwrap();
+        }
+        out
+    }
+
+    /// The symmetry undoing this one
+    pub fn inverse(&self) -> Symmetry {
+        let geometry = match self.geometry {
+            1 => 3,
+            3 => 1,
+            g => g,
+        };
+        let mut permutation = [0; 4];
+        for (i, &p) in self.permutation.iter().enumerate() {
+            permutation[p as usize] = i as u8;
+        }
+        // Complementing then permuting equals permuting then complementing the permuted bits
+        let complement = (0..4)
+            .filter(|&i| self.complement & 1 << i != 0)
+            .fold(0, |x, i| x | 1 << self.permutation[i]);
+        Symmetry {
+            geometry,
+            permutation,
+            complement,
+        }
+    }
+}
+
+/// Every ordering of the 4 attributes
+fn permutations() -> impl Iterator<Item = [u8; 4]> + Clone {
+    (0..4u8).flat_map(|a| {
+        (0..4u8).flat_map(move |b| {
+            (0..4u8).flat_map(move |c| {
+                (0..4u8)
+                    .map(move |d| [a, b, c, d])
+                    .filter(|p| (0..4).all(|x| p.contains(&x)))
+            })
+        })
+    })
+}
+
+impl Board {
+    /// Canonical representative of all positions equivalent to this one under [`Symmetry`],
+    /// along with the symmetry mapping this board onto it
+    pub fn canonical(&self) -> (Board, Symmetry) {
+        const EMPTY: u8 = 16;
+
+        let squares = (0..16)
+            .map(|i| self.get_square_index(i).map(|p| (i, p.index().unwrap())))
+            .collect::<Vec<_>>();
+        let nominated = self.nominated_index();
+
+        // Squares in order followed by the nominated piece, smallest key wins
+        let (_, symmetry) = Symmetry::all()
+            .map(|symmetry| {
+                let mut key = [EMPTY; 17];
+                for &(i, piece) in squares.iter().flatten() {
+                    key[symmetry.square(i)] = symmetry.piece(piece) as u8;
+                }
+                if let Some(n) = nominated {
+                    key[16] = symmetry.piece(n) as u8;
+                }
+                (key, symmetry)
+            })
+            .min_by_key(|(key, _)| *key)
+            .unwrap();
+
+        (symmetry.board(self), symmetry)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashSet;
+
+    use crate::{board::Board, game::tests::play_piece, position::Position, symmetry::Symmetry};
+
+    fn position() -> Board {
+        let mut board = Board::new();
+        play_piece(&mut board, 3, 0, 1);
+        play_piece(&mut board, 9, 1, 1);
+        play_piece(&mut board, 14, 2, 3);
+        play_piece(&mut board, 6, 3, 2);
+        board.nominate(12).unwrap()
+    }
+
+    #[test]
+    pub fn symmetries_preserve_lines() {
+        assert_eq!(Symmetry::all().count(), 3072);
+        let quartos = Board::quartos()
+            .map(|mut xs| {
+                xs.sort();
+                xs
+            })
+            .into_iter()
+            .collect::<HashSet<_>>();
+        for geometry in Symmetry::all().step_by(384) {
+            for line in Board::quartos() {
+                let mut image = line.map(|x| geometry.square(x));
+                image.sort();
+                assert!(quartos.contains(&image), "{geometry:?} breaks {line:?}");
+            }
+        }
+    }
+
+    #[test]
+    pub fn inverse() {
+        let board = position();
+        for symmetry in Symmetry::all() {
+            assert_eq!(symmetry.inverse().board(&symmetry.board(&board)), board);
+            let pos = Position::from_index(7usize).unwrap();
+            assert_eq!(symmetry.inverse().position(symmetry.position(pos)), pos);
+        }
+    }
+
+    #[test]
+    pub fn canonical_is_invariant() {
+        let board = position();
+        let (canonical, symmetry) = board.canonical();
+        assert_eq!(symmetry.board(&board), canonical);
+        for other in Symmetry::all().step_by(7) {
+            let image = other.board(&board);
+            assert_eq!(image.detect_win(), board.detect_win());
+            assert_eq!(image.canonical().0, canonical);
+        }
+    }
+
+    #[test]
+    pub fn single_piece_classes() {
+        let classes = (0..16)
+            .flat_map(|piece| {
+                (0..16usize).map(move |square| {
+                    let mut board = Board::new();
+                    play_piece(&mut board, piece, square / 4, square % 4);
+                    board.canonical().0
+                })
+            })
+            .collect::<HashSet<_>>();
+        // Corners, edges and centre squares
+        assert_eq!(classes.len(), 3);
+    }
+}
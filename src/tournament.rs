@@ -0,0 +1,414 @@
+use std::fmt::Display;
+
+use crate::{
+    board::Board, clock::TimeControl, game::Game, player::QuartoPlayer, runner::GameOutcome,
+};
+
+/// Which entrants play each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pairing {
+    /// Every entrant plays every other
+    #[default]
+    RoundRobin,
+    /// The first entrant plays every other, who do not play each other
+    Gauntlet,
+}
+
+struct Entrant {
+    name: String,
+    factory: Box<dyn Fn() -> Box<dyn QuartoPlayer>>,
+}
+
+/// Plays pairs of games between entrants, swapping who nominates first between the games of
+/// each pair
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    pairing: Pairing,
+    rounds: usize,
+    time_control: TimeControl,
+    /// Starting boards, taken in turn by each pair of games
+    openings: Vec<Board>,
+}
+
+impl Tournament {
+    /// Each pairing plays `rounds` pairs of games
+    pub fn new(pairing: Pairing, rounds: usize) -> Self {
+        Tournament {
+            entrants: Vec::new(),
+            pairing,
+            rounds,
+            time_control: TimeControl::default(),
+            openings: Vec::new(),
+        }
+    }
+
+    pub fn with_time_control(mut self, time_control: TimeControl) -> Self {
+        self.time_control = time_control;
+        self
+    }
+
+    /// Plays each pair of games from the next of the boards rather than the empty board
+    pub fn with_openings(mut self, openings: Vec<Board>) -> Self {
+        self.openings = openings;
+        self
+    }
+
+    pub fn entrant<P: QuartoPlayer>(
+        mut self,
+        name: impl Into<String>,
+        factory: impl Fn() -> P + 'static,
+    ) -> Self {
+        self.entrants.push(Entrant {
+            name: name.into(),
+            factory: Box::new(move || Box::new(factory())),
+        });
+        self
+    }
+
+    /// Pairs of entrants to play, by index
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let n = self.entrants.len();
+        match self.pairing {
+            Pairing::RoundRobin => (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .collect(),
+            Pairing::Gauntlet => (1..n).map(|j| (0, j)).collect(),
+        }
+    }
+
+    pub async fn run(&self) -> TournamentResult {
+        self.run_with(|_| ()).await
+    }
+
+    /// Plays the tournament like [`Tournament::run`], passing each game to `on_game` as it
+    /// finishes
+    pub async fn run_with(&self, mut on_game: impl FnMut(&GameOutcome)) -> TournamentResult {
+        let n = self.entrants.len();
+        let mut scores = vec![vec![Score::default(); n]; n];
+        let mut errors = 0;
+        let mut played = 0;
+
+        for (a, b) in self.pairings() {
+            for _ in 0..self.rounds {
+                let start = match self.openings.len() {
+                    0 => Board::new(),
+                    n => self.openings[played / 2 % n],
+                };
+                // The second seat nominates first
+                for seats in [[a, b], [b, a]] {
+                    let mut game = Game::new(
+                        (self.entrants[seats[0]].factory)(),
+                        (self.entrants[seats[1]].factory)(),
+                    )
+                    .with_start(start)
+                    .with_time_control(self.time_control);
+                    let outcome = (played, game.run().await);
+                    played += 1;
+                    on_game(&outcome);
+                    match outcome.1 {
+                        Ok((result, _)) => {
+                            let winner = result.winner().map(|x| seats[x]);
+                            scores[a][b].add(winner.map(|x| x == a));
+                            scores[b][a].add(winner.map(|x| x == b));
+                        }
+                        Err(_) => errors += 1,
+                    }
+                }
+            }
+        }
+
+        TournamentResult {
+            names: self.entrants.iter().map(|x| x.name.clone()).collect(),
+            ratings: ratings(&scores),
+            scores,
+            errors,
+        }
+    }
+}
+
+/// Results of one entrant against another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Score {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Score {
+    /// Adds a game, `None` being a draw
+    pub fn add(&mut self, won: Option<bool>) {
+        match won {
+            Some(true) => self.wins += 1,
+            None => self.draws += 1,
+            Some(false) => self.losses += 1,
+        }
+    }
+
+    #[inline]
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Wins plus half the draws
+    #[inline]
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+}
+
+impl std::ops::AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        self.wins += rhs.wins;
+        self.draws += rhs.draws;
+        self.losses += rhs.losses;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rating {
+    /// Relative to the average entrant
+    pub elo: f64,
+    /// Half width of the approximate 95% confidence interval
+    pub error: f64,
+}
+
+/// Bradley-Terry ratings on the Elo scale from a crosstable of scores
+///
+/// Strengths are fitted by minorization-maximization with draws counted as half a win for each
+/// side, and one virtual draw added to every pairing which has played so that perfect scores
+/// still have finite ratings. Error bars come from the Fisher information of each rating with
+/// the others held fixed
+pub fn ratings(scores: &[Vec<Score>]) -> Vec<Rating> {
+    const ITERATIONS: usize = 10_000;
+    const TOLERANCE: f64 = 1e-10;
+    let elo = 400.0 / std::f64::consts::LN_10;
+
+    let n = scores.len();
+    let games = |i: usize, j: usize| match scores[i][j].games() {
+        0 => 0.0,
+        games => games as f64 + 1.0,
+    };
+    let points = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| scores[i][j].points() + games(i, j).min(1.0) / 2.0)
+                .sum()
+        })
+        .collect::<Vec<f64>>();
+
+    let mut strength = vec![1.0; n];
+    for _ in 0..ITERATIONS {
+        let mut next = (0..n)
+            .map(|i| {
+                let denominator: f64 = (0..n)
+                    .map(|j| games(i, j) / (strength[i] + strength[j]))
+                    .sum();
+                match denominator > 0.0 {
+                    true => points[i] / denominator,
+                    false => strength[i],
+                }
+            })
+            .collect::<Vec<_>>();
+        // Only differences in rating are meaningful, so fix the geometric mean
+        let mean = next.iter().map(|x: &f64| x.ln()).sum::<f64>() / n as f64;
+        next.iter_mut().for_each(|x| *x /= mean.exp());
+
+        let change = next
+            .iter()
+            .zip(strength.iter())
+            .map(|(a, b)| (a.ln() - b.ln()).abs())
+            .fold(0.0, f64::max);
+        strength = next;
+        if change < TOLERANCE {
+            break;
+        }
+    }
+
+    (0..n)
+        .map(|i| {
+            let information: f64 = (0..n)
+                .map(|j| {
+                    let p = strength[i] / (strength[i] + strength[j]);
+                    games(i, j) * p * (1.0 - p)
+                })
+                .sum();
+            Rating {
+                elo: elo * strength[i].ln(),
+                error: 1.96 * elo / information.sqrt(),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    /// Crosstable of each entrant's score against each other
+    pub scores: Vec<Vec<Score>>,
+    pub ratings: Vec<Rating>,
+    /// Games which ended in an error and were not scored
+    pub errors: usize,
+}
+
+impl TournamentResult {
+    /// Score of the entrant against everyone
+    pub fn total(&self, i: usize) -> Score {
+        let mut total = Score::default();
+        self.scores[i].iter().for_each(|&x| total += x);
+        total
+    }
+
+    /// Entrant indexes from highest to lowest rated
+    pub fn standings(&self) -> Vec<usize> {
+        let mut order = (0..self.names.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| self.ratings[b].elo.total_cmp(&self.ratings[a].elo));
+        order
+    }
+}
+
+impl Display for TournamentResult {
+    /// Crosstable in order of rating, each cell being wins-draws-losses against that column
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let order = self.standings();
+        let width = self.names.iter().map(|x| x.len()).max().unwrap_or(0).max(4);
+        let cells = order
+            .iter()
+            .map(|&i| {
+                order
+                    .iter()
+                    .map(|&j| match (i == j, self.scores[i][j]) {
+                        (true, _) => "-".to_string(),
+                        (false, x) if x.games() == 0 => ".".to_string(),
+                        (false, x) => format!("{}-{}-{}", x.wins, x.draws, x.losses),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let cell = cells
+            .iter()
+            .flatten()
+            .map(|x| x.len())
+            .max()
+            .unwrap_or(0)
+            .max(3);
+
+        write!(
+            f,
+            "{:>3}  {:<width$} {:>6} {:>5} {:>6} {:>6}",
+            "#", "Name", "Elo", "+/-", "Games", "Score"
+        )?;
+        for rank in 1..=order.len() {
+            write!(f, "  {rank:>cell$}")?;
+        }
+        writeln!(f)?;
+        for (rank, &i) in order.iter().enumerate() {
+            let rating = self.ratings[i];
+            let total = self.total(i);
+            write!(
+                f,
+                "{:>3}  {:<width$} {:>+6.0} {:>5.0} {:>6} {:>6.1}",
+                rank + 1,
+                self.names[i],
+                rating.elo,
+                rating.error,
+                total.games(),
+                total.points()
+            )?;
+            for x in cells[rank].iter() {
+                write!(f, "  {x:>cell$}")?;
+            }
+            writeln!(f)?;
+        }
+        if self.errors > 0 {
+            writeln!(f, "{} games ended in an error", self.errors)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        board::Board,
+        game::tests::ScriptedPlayer,
+        player::RandomPlayer,
+        solver::tests::endgame,
+        tournament::{ratings, Pairing, Score, Tournament},
+    };
+
+    fn score(wins: usize, draws: usize, losses: usize) -> Score {
+        Score {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    pub fn bradley_terry() {
+        // A 75% score is about 191 Elo
+        let scores = vec![
+            vec![score(0, 0, 0), score(150, 0, 50)],
+            vec![score(50, 0, 150), score(0, 0, 0)],
+        ];
+        let rated = ratings(&scores);
+        let difference = rated[0].elo - rated[1].elo;
+        assert!((difference - 191.0).abs() < 3.0, "{difference}");
+        assert!((rated[0].elo + rated[1].elo).abs() < 1e-6);
+        assert!(rated[0].error > 50.0 && rated[0].error < 150.0);
+
+        // Perfect scores and draws still rate, even and transitive
+        let scores = vec![
+            vec![score(0, 0, 0), score(10, 0, 0), score(0, 10, 0)],
+            vec![score(0, 0, 10), score(0, 0, 0), score(0, 0, 0)],
+            vec![score(0, 10, 0), score(0, 0, 0), score(0, 0, 0)],
+        ];
+        let rated = ratings(&scores);
+        assert!(rated
+            .iter()
+            .all(|x| x.elo.is_finite() && x.error.is_finite()));
+        assert!(rated[0].elo > rated[2].elo && rated[2].elo > rated[1].elo);
+    }
+
+    #[test]
+    pub fn swaps_colours() {
+        let tournament = Tournament::new(Pairing::RoundRobin, 2)
+            .entrant("a", || RandomPlayer)
+            .entrant("b", || RandomPlayer)
+            .entrant("c", || RandomPlayer);
+        assert_eq!(tournament.pairings(), vec![(0, 1), (0, 2), (1, 2)]);
+
+        let result = pollster::block_on(tournament.run());
+        for i in 0..3 {
+            assert_eq!(result.total(i).games(), 8);
+        }
+        assert_eq!(result.scores[0][1].wins, result.scores[1][0].losses);
+        assert!(result.to_string().contains("Elo"));
+
+        // Whoever places first then nominates the piece they placed and forfeits
+        let scripted = || ScriptedPlayer::new(&[0], &[0]);
+        let tournament = Tournament::new(Pairing::Gauntlet, 3)
+            .entrant("first", scripted)
+            .entrant("second", scripted);
+        let result = pollster::block_on(tournament.run());
+        assert_eq!(result.scores[0][1], score(3, 0, 3));
+        assert!(result.ratings[0].elo.abs() < 1e-6);
+    }
+
+    #[test]
+    pub fn plays_openings() {
+        let tournament = Tournament::new(Pairing::RoundRobin, 3)
+            .with_openings(vec![endgame(), Board::new()])
+            .entrant("a", || RandomPlayer)
+            .entrant("b", || RandomPlayer);
+        let mut starts = Vec::new();
+        pollster::block_on(tournament.run_with(|(_, game)| {
+            starts.push(game.as_ref().unwrap().1.start);
+        }));
+        let (a, b) = (endgame(), Board::new());
+        assert_eq!(starts, vec![a, a, b, b, a, a]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How the stored value relates to the true value of the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The true value is at least the stored value
+    Lower,
+    /// The true value is at most the stored value
+    Upper,
+}
+
+/// Result of a search stored against a position
+///
+/// `best` is a square index when the position has a nominated piece and a piece index otherwise
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    pub value: f32,
+    pub depth: u8,
+    pub bound: Bound,
+    pub best: Option<u8>,
+}
+
+impl Entry {
+    const NO_MOVE: u64 = 0xFF;
+    /// Set on every packed entry so an empty slot never matches
+    const OCCUPIED: u64 = 1 << 24;
+
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let best = self.best.map_or(Self::NO_MOVE, |x| x as u64);
+        (self.value.to_bits() as u64) << 32
+            | Self::OCCUPIED
+            | (self.depth as u64) << 16
+            | bound << 8
+            | best
+    }
+
+    fn unpack(data: u64) -> Self {
+        let bound = match (data >> 8) & 0xFF {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        let best = data & 0xFF;
+        Entry {
+            value: f32::from_bits((data >> 32) as u32),
+            depth: (data >> 16) as u8,
+            bound,
+            best: (best != Self::NO_MOVE).then_some(best as u8),
+        }
+    }
+}
+
+/// Slot storing `key ^ data` alongside `data`, a torn write from another thread fails the key
+/// check rather than returning a corrupted entry
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Fixed size, lock free transposition table which can be shared between search threads
+pub struct TranspositionTable {
+    slots: Box<[Slot]>,
+    mask: usize,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_CAPACITY: usize = 1 << 18;
+
+    /// Creates a table with `capacity` rounded up to the next power of two
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1).next_power_of_two();
+        TranspositionTable {
+            slots: (0..capacity).map(|_| Slot::default()).collect(),
+            mask: capacity - 1,
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    #[inline]
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[key as usize & self.mask]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        let check = slot.check.load(Ordering::Relaxed);
+        (data != 0 && check ^ data == key).then(|| Entry::unpack(data))
+    }
+
+    /// Stores the entry, an entry for the same key is only replaced by one searched at least as deep
+    pub fn store(&self, key: u64, entry: Entry) {
+        let slot = self.slot(key);
+        if let Some(old) = self.probe(key) {
+            if old.depth > entry.depth {
+                return;
+            }
+        }
+        let data = entry.pack();
+        slot.data.store(data, Ordering::Relaxed);
+        slot.check.store(key ^ data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.data.store(0, Ordering::Relaxed);
+            slot.check.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(TranspositionTable::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        board::Board,
+        game::tests::play_piece,
+        transposition::{Bound, Entry, TranspositionTable},
+    };
+
+    #[test]
+    pub fn store_and_probe() {
+        let table = TranspositionTable::new(1000);
+        assert_eq!(table.capacity(), 1024);
+
+        let entry = Entry {
+            value: -3.5,
+            depth: 4,
+            bound: Bound::Lower,
+            best: Some(7),
+        };
+        table.store(42, entry);
+        assert_eq!(table.probe(42), Some(entry));
+        assert_eq!(table.probe(42 + 1024), None);
+
+        let shallow = Entry { depth: 2, ..entry };
+        table.store(42, shallow);
+        assert_eq!(table.probe(42), Some(entry));
+
+        let infinite = Entry {
+            value: f32::INFINITY,
+            best: None,
+            ..entry
+        };
+        table.store(42, infinite);
+        assert_eq!(table.probe(42), Some(infinite));
+
+        table.clear();
+        assert_eq!(table.probe(42), None);
+    }
+
+    #[test]
+    pub fn hash_transpositions() {
+        let mut a = Board::new();
+        play_piece(&mut a, 0, 0, 0);
+        play_piece(&mut a, 1, 1, 1);
+
+        let mut b = Board::new();
+        play_piece(&mut b, 1, 1, 1);
+        play_piece(&mut b, 0, 0, 0);
+        assert_eq!(a.zobrist(), b.zobrist());
+
+        let mut c = Board::new();
+        play_piece(&mut c, 1, 0, 0);
+        play_piece(&mut c, 0, 1, 1);
+        assert_ne!(a.zobrist(), c.zobrist());
+
+        assert_ne!(a.zobrist(), a.nominate(2).unwrap().zobrist());
+        assert_ne!(
+            a.nominate(2).unwrap().zobrist(),
+            a.nominate(3).unwrap().zobrist()
+        );
+    }
+}